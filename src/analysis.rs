@@ -0,0 +1,353 @@
+//! Heuristic dialog quality metrics
+//!
+//! These are cheap signals for comparing runs, not a verdict on quality:
+//! line length, the "one sentence" rule from the system prompt, n-gram
+//! diversity, repetition, question/statement balance, how distinct the two
+//! voices are, and how often each speaker's style cues show up.
+
+use crate::config::Speaker;
+use crate::orchestrator::DialogExchange;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// Words too common to say anything about a speaker's voice
+const STOPWORDS: &[&str] = &[
+    "the", "and", "that", "this", "you", "for", "are", "was", "with", "but", "not", "have", "just",
+    "its", "it's", "what", "about", "all", "been", "they", "from", "i'm", "your", "like",
+];
+
+/// Metrics for one speaker
+#[derive(Debug, Clone)]
+pub struct SpeakerMetrics {
+    pub name: String,
+    pub lines: usize,
+    pub avg_words_per_line: f64,
+    /// Occurrences of each quoted cue from the speaker's speaking style
+    pub style_cues: Vec<(String, usize)>,
+    /// Lines containing at least one style cue
+    pub lines_with_cues: usize,
+}
+
+/// Metrics for a whole dialog
+#[derive(Debug, Clone)]
+pub struct DialogAnalysis {
+    pub lines: usize,
+    pub speakers: Vec<SpeakerMetrics>,
+    /// Lines with more than one sentence
+    pub one_sentence_violations: usize,
+    pub questions: usize,
+    pub statements: usize,
+    /// Unique words / total words
+    pub distinct_1: f64,
+    /// Unique word bigrams / total bigrams
+    pub distinct_2: f64,
+    /// Share of each speaker's trigrams already used in their earlier lines
+    pub self_repetition: f64,
+    /// Jaccard overlap of the speakers' content vocabularies (lower = more distinct voices)
+    pub speaker_overlap: f64,
+}
+
+/// Analyze a dialog. Style cues are counted for speakers found in `speakers`;
+/// other speakers still get line and word counts.
+pub fn analyze(exchanges: &[DialogExchange], speakers: &[Speaker]) -> DialogAnalysis {
+    let mut order: Vec<&str> = Vec::new();
+    let mut by_speaker: HashMap<&str, Vec<&str>> = HashMap::new();
+    for exchange in exchanges {
+        let name = exchange.speaker.as_str();
+        if !by_speaker.contains_key(name) {
+            order.push(name);
+        }
+        by_speaker.entry(name).or_default().push(&exchange.content);
+    }
+
+    let speaker_metrics = order
+        .iter()
+        .map(|name| {
+            let lines = &by_speaker[name];
+            let words: usize = lines.iter().map(|l| l.split_whitespace().count()).sum();
+            let cues = speakers
+                .iter()
+                .find(|s| s.name == *name)
+                .map(|s| style_cues(&s.speaking_style))
+                .unwrap_or_default();
+
+            let style_cues: Vec<(String, usize)> = cues
+                .iter()
+                .map(|cue| (cue.clone(), lines.iter().map(|l| count_cue(l, cue)).sum()))
+                .collect();
+            let lines_with_cues = lines
+                .iter()
+                .filter(|l| cues.iter().any(|cue| count_cue(l, cue) > 0))
+                .count();
+
+            SpeakerMetrics {
+                name: name.to_string(),
+                lines: lines.len(),
+                avg_words_per_line: ratio(words, lines.len()),
+                style_cues,
+                lines_with_cues,
+            }
+        })
+        .collect();
+
+    let tokenized: Vec<Vec<String>> = exchanges.iter().map(|e| tokens(&e.content)).collect();
+    let questions = exchanges.iter().filter(|e| is_question(&e.content)).count();
+
+    DialogAnalysis {
+        lines: exchanges.len(),
+        speakers: speaker_metrics,
+        one_sentence_violations: exchanges
+            .iter()
+            .filter(|e| sentence_count(&e.content) > 1)
+            .count(),
+        questions,
+        statements: exchanges.len() - questions,
+        distinct_1: distinct_n(&tokenized, 1),
+        distinct_2: distinct_n(&tokenized, 2),
+        self_repetition: self_repetition(exchanges, &tokenized),
+        speaker_overlap: speaker_overlap(exchanges, &tokenized, &order),
+    }
+}
+
+impl DialogAnalysis {
+    /// Format as "Key: value" lines, matching the metadata files
+    pub fn format(&self) -> String {
+        let mut out = format!(
+            "One-sentence violations: {}/{}\n\
+             Questions/statements: {}/{}\n\
+             Distinct-1: {:.2}\n\
+             Distinct-2: {:.2}\n\
+             Self-repetition: {:.2}\n\
+             Speaker overlap: {:.2}\n",
+            self.one_sentence_violations,
+            self.lines,
+            self.questions,
+            self.statements,
+            self.distinct_1,
+            self.distinct_2,
+            self.self_repetition,
+            self.speaker_overlap,
+        );
+
+        for speaker in &self.speakers {
+            let _ = write!(
+                out,
+                "{}: {} lines, {:.1} words/line",
+                speaker.name, speaker.lines, speaker.avg_words_per_line
+            );
+            if !speaker.style_cues.is_empty() {
+                let cues: Vec<String> = speaker
+                    .style_cues
+                    .iter()
+                    .map(|(cue, count)| format!("\"{}\" {}", cue, count))
+                    .collect();
+                let _ = write!(
+                    out,
+                    ", style cues in {}/{} lines ({})",
+                    speaker.lines_with_cues,
+                    speaker.lines,
+                    cues.join(", ")
+                );
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+/// Quoted phrases in a speaking style description, e.g. `uses "like" and
+/// "honestly"` gives ["like", "honestly"]
+pub fn style_cues(speaking_style: &str) -> Vec<String> {
+    let mut cues: Vec<String> = Vec::new();
+    for cue in speaking_style.split('"').skip(1).step_by(2) {
+        let cue = cue.trim().to_lowercase();
+        if !cue.is_empty() && !cues.contains(&cue) {
+            cues.push(cue);
+        }
+    }
+    cues
+}
+
+/// Count case-insensitive occurrences of a cue, respecting word boundaries
+fn count_cue(line: &str, cue: &str) -> usize {
+    let line = line.to_lowercase();
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric());
+
+    line.match_indices(cue)
+        .filter(|(pos, _)| {
+            let before = line[..*pos].chars().next_back();
+            let after = line[pos + cue.len()..].chars().next();
+            let starts_word = is_word(cue.chars().next());
+            let ends_word = is_word(cue.chars().next_back());
+            let joined_before = starts_word && is_word(before);
+            let joined_after = ends_word && is_word(after);
+            !joined_before && !joined_after
+        })
+        .count()
+}
+
+/// Count sentences by terminal punctuation followed by more text.
+/// A trailing-off ellipsis doesn't end a sentence.
+fn sentence_count(line: &str) -> usize {
+    let chars: Vec<char> = line.trim().chars().collect();
+    let mut count = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        if matches!(chars[i], '.' | '!' | '?') {
+            let start = i;
+            while i < chars.len() && matches!(chars[i], '.' | '!' | '?') {
+                i += 1;
+            }
+            let ellipsis = chars[start..i].iter().filter(|&&c| c == '.').count() >= 3;
+            let more_text = chars[i..].iter().any(|c| c.is_alphanumeric());
+            if !ellipsis && more_text && chars.get(i).is_some_and(|c| c.is_whitespace()) {
+                count += 1;
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    count
+}
+
+fn is_question(line: &str) -> bool {
+    line.trim_end_matches(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | ')' | '.'))
+        .ends_with('?')
+}
+
+/// Lowercased words with surrounding punctuation stripped
+pub(crate) fn tokens(line: &str) -> Vec<String> {
+    line.split_whitespace()
+        .map(|w| {
+            w.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'')
+                .to_lowercase()
+        })
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+fn ngrams(words: &[String], n: usize) -> impl Iterator<Item = String> + '_ {
+    words.windows(n).map(|w| w.join(" "))
+}
+
+fn distinct_n(lines: &[Vec<String>], n: usize) -> f64 {
+    let all: Vec<String> = lines.iter().flat_map(|l| ngrams(l, n)).collect();
+    let unique: HashSet<&String> = all.iter().collect();
+    ratio(unique.len(), all.len())
+}
+
+fn self_repetition(exchanges: &[DialogExchange], lines: &[Vec<String>]) -> f64 {
+    let mut seen: HashMap<&str, HashSet<String>> = HashMap::new();
+    let mut repeated = 0;
+    let mut total = 0;
+
+    for (exchange, words) in exchanges.iter().zip(lines) {
+        let earlier = seen.entry(exchange.speaker.as_str()).or_default();
+        let trigrams: Vec<String> = ngrams(words, 3).collect();
+        total += trigrams.len();
+        repeated += trigrams.iter().filter(|t| earlier.contains(*t)).count();
+        earlier.extend(trigrams);
+    }
+
+    ratio(repeated, total)
+}
+
+fn speaker_overlap(exchanges: &[DialogExchange], lines: &[Vec<String>], order: &[&str]) -> f64 {
+    let [first, second, ..] = order else {
+        return 0.0;
+    };
+
+    let vocabulary = |name: &str| -> HashSet<&str> {
+        exchanges
+            .iter()
+            .zip(lines)
+            .filter(|(e, _)| e.speaker == name)
+            .flat_map(|(_, words)| words.iter().map(String::as_str))
+            .filter(|w| w.len() > 2 && !STOPWORDS.contains(w))
+            .collect()
+    };
+
+    let a = vocabulary(first);
+    let b = vocabulary(second);
+    ratio(a.intersection(&b).count(), a.union(&b).count())
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange(speaker: &str, content: &str) -> DialogExchange {
+        DialogExchange {
+            speaker: speaker.to_string(),
+            content: content.to_string(),
+            stats: None,
+        }
+    }
+
+    #[test]
+    fn counts_lines_questions_and_sentences() {
+        let exchanges = [
+            exchange("Maya", "Did you like it?"),
+            exchange("Dev", "Loved it. Best book all year."),
+            exchange("Maya", "Wait... really?"),
+        ];
+        let analysis = analyze(&exchanges, &[]);
+
+        assert_eq!(analysis.lines, 3);
+        assert_eq!(analysis.questions, 2);
+        assert_eq!(analysis.statements, 1);
+        assert_eq!(analysis.one_sentence_violations, 1);
+        assert_eq!(analysis.speakers[0].name, "Maya");
+        assert_eq!(analysis.speakers[0].lines, 2);
+        assert_eq!(analysis.speakers[0].avg_words_per_line, 3.0);
+    }
+
+    #[test]
+    fn counts_style_cues_on_word_boundaries() {
+        let speakers = [Speaker::new("Maya").with_speaking_style(r#"Says "like" and "honestly""#)];
+        let exchanges = [
+            exchange("Maya", "Like, honestly, I liked it."),
+            exchange("Maya", "It was likeable."),
+        ];
+        let analysis = analyze(&exchanges, &speakers);
+
+        assert_eq!(
+            analysis.speakers[0].style_cues,
+            [("like".to_string(), 1), ("honestly".to_string(), 1)]
+        );
+        assert_eq!(analysis.speakers[0].lines_with_cues, 1);
+    }
+
+    #[test]
+    fn measures_repetition_and_overlap() {
+        let exchanges = [
+            exchange("Maya", "the plot twist was great"),
+            exchange("Dev", "totally different words here"),
+            exchange("Maya", "the plot twist was great"),
+        ];
+        let analysis = analyze(&exchanges, &[]);
+
+        assert_eq!(analysis.self_repetition, 3.0 / 8.0);
+        assert_eq!(analysis.speaker_overlap, 0.0);
+        assert_eq!(analysis.distinct_1, 9.0 / 14.0);
+    }
+
+    #[test]
+    fn empty_dialog_has_zero_metrics() {
+        let analysis = analyze(&[], &[]);
+        assert_eq!(analysis.lines, 0);
+        assert_eq!(analysis.distinct_1, 0.0);
+        assert!(analysis.speakers.is_empty());
+    }
+}
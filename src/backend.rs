@@ -0,0 +1,111 @@
+//! LLM backend abstraction
+//!
+//! The orchestrator and podcast editor only need a model name and a chat
+//! call, so they run against any `ChatBackend`. `OllamaClient` is the
+//! production backend; tests substitute a deterministic one.
+
+use crate::error::Result;
+use crate::ollama::{ChatMessage, ChatOptions, ChatResult, OllamaClient};
+use std::future::Future;
+
+/// A chat-completion backend
+pub trait ChatBackend: Send + Sync {
+    /// Model name reported in metadata
+    fn model(&self) -> &str;
+
+    /// Send a system prompt plus conversation and return the reply
+    fn chat(
+        &self,
+        system_prompt: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> impl Future<Output = Result<ChatResult>> + Send;
+
+    /// Like `chat`, but asks for a reply that is valid JSON. Backends that
+    /// can't constrain output rely on the prompt asking for JSON.
+    fn chat_json(
+        &self,
+        system_prompt: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> impl Future<Output = Result<ChatResult>> + Send {
+        self.chat(system_prompt, messages, options)
+    }
+
+    /// Like `chat`, but calls `on_token` with each chunk of the reply as it
+    /// arrives. Backends without streaming deliver the whole reply at once.
+    fn chat_stream(
+        &self,
+        system_prompt: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        on_token: &(dyn Fn(&str) + Send + Sync),
+    ) -> impl Future<Output = Result<ChatResult>> + Send {
+        async move {
+            let result = self.chat(system_prompt, messages, options).await?;
+            on_token(&result.content);
+            Ok(result)
+        }
+    }
+}
+
+/// Deterministic backend for unit tests: every reply comes from a closure
+/// given the system prompt and messages
+#[cfg(test)]
+pub(crate) struct CannedBackend<F>(pub(crate) F);
+
+#[cfg(test)]
+impl<F> ChatBackend for CannedBackend<F>
+where
+    F: Fn(&str, &[ChatMessage]) -> Result<String> + Send + Sync,
+{
+    fn model(&self) -> &str {
+        "canned"
+    }
+
+    async fn chat(
+        &self,
+        system_prompt: &str,
+        messages: &[ChatMessage],
+        _options: &ChatOptions,
+    ) -> Result<ChatResult> {
+        Ok(ChatResult {
+            content: (self.0)(system_prompt, messages)?,
+            stats: Default::default(),
+        })
+    }
+}
+
+impl ChatBackend for OllamaClient {
+    fn model(&self) -> &str {
+        OllamaClient::model(self)
+    }
+
+    fn chat(
+        &self,
+        system_prompt: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> impl Future<Output = Result<ChatResult>> + Send {
+        OllamaClient::chat(self, system_prompt, messages, options)
+    }
+
+    fn chat_json(
+        &self,
+        system_prompt: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> impl Future<Output = Result<ChatResult>> + Send {
+        OllamaClient::chat_json(self, system_prompt, messages, options)
+    }
+
+    fn chat_stream(
+        &self,
+        system_prompt: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        on_token: &(dyn Fn(&str) + Send + Sync),
+    ) -> impl Future<Output = Result<ChatResult>> + Send {
+        OllamaClient::chat_stream(self, system_prompt, messages, options, on_token)
+    }
+}
@@ -0,0 +1,282 @@
+//! Batch generation across many scene directories
+//!
+//! Each scene is loaded and generated independently with a concurrency
+//! limit. Failures are recorded per scene instead of stopping the batch.
+//! With an output root, each scene writes to a directory named after the
+//! end of its path, long enough to tell scenes with the same name apart
+//! (`a/scene1` and `b/scene1` rather than two `scene1`).
+
+use crate::config::DialogConfig;
+use crate::error::{DialogGenError, Result};
+use crate::manifest::RunManifest;
+use crate::ollama::OllamaClient;
+use crate::orchestrator::DialogOrchestrator;
+use crate::output::{OutputLayout, OutputWriter};
+use crate::settings::{Settings, SettingsLayer};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Result of generating one scene
+#[derive(Debug)]
+pub struct SceneOutcome {
+    pub input_dir: PathBuf,
+    pub elapsed: Duration,
+    pub result: std::result::Result<SceneSuccess, String>,
+}
+
+#[derive(Debug)]
+pub struct SceneSuccess {
+    pub output_path: PathBuf,
+    pub model: String,
+    pub turns: usize,
+    pub total_tokens: u64,
+    pub tokens_per_second: f64,
+    /// See [`OutputWriter::latest_link_error`]
+    pub latest_link_error: Option<String>,
+}
+
+/// Expand glob patterns into scene directories, in sorted order.
+/// Matches that aren't directories are skipped.
+pub fn expand_patterns(patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+
+    for pattern in patterns {
+        let paths = glob::glob(pattern)
+            .map_err(|e| DialogGenError::InvalidConfig(format!("{}: {}", pattern, e)))?;
+        dirs.extend(paths.flatten().filter(|p| p.is_dir()));
+    }
+
+    dirs.sort();
+    dirs.dedup();
+    Ok(dirs)
+}
+
+/// Read a manifest with one scene directory per line. Blank lines and lines
+/// starting with '#' are ignored; relative paths are relative to the manifest.
+pub fn read_manifest(path: &Path) -> Result<Vec<PathBuf>> {
+    let content = fs::read_to_string(path)?;
+    let base = path.parent().unwrap_or(Path::new("."));
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| base.join(line))
+        .collect())
+}
+
+/// Drop scene directories that are listed more than once, including the
+/// same directory reached by different paths. The first listing is kept.
+pub fn dedupe(dirs: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    dirs.into_iter()
+        .filter(|dir| seen.insert(dir.canonicalize().unwrap_or_else(|_| dir.clone())))
+        .collect()
+}
+
+/// Output directory names for `dirs` under an output root: the last path
+/// component, with parent components added until no two scenes share a name
+pub fn output_names(dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let components: Vec<Vec<_>> = dirs
+        .iter()
+        .map(|dir| {
+            let path = dir.canonicalize().unwrap_or_else(|_| dir.clone());
+            path.components()
+                .filter_map(|c| match c {
+                    Component::Normal(name) => Some(name.to_os_string()),
+                    _ => None,
+                })
+                .collect()
+        })
+        .collect();
+    let suffix = |parts: &[OsString], len: usize| -> PathBuf {
+        parts[parts.len().saturating_sub(len)..].iter().collect()
+    };
+
+    components
+        .iter()
+        .map(|parts| {
+            (1..=parts.len())
+                .map(|len| suffix(parts, len))
+                .find(|name| {
+                    components
+                        .iter()
+                        .filter(|other| *other != parts)
+                        .all(|other| suffix(other, name.components().count()) != *name)
+                })
+                .unwrap_or_else(|| suffix(parts, parts.len()))
+        })
+        .collect()
+}
+
+/// Generate every scene, running at most `concurrency` at once and
+/// retrying a failed or empty turn up to `max_retries` times, and call
+/// `on_outcome` as each one finishes. Outcomes are returned in the order of
+/// `dirs`.
+pub async fn run<F>(
+    dirs: Vec<PathBuf>,
+    cli: SettingsLayer,
+    output_root: Option<PathBuf>,
+    layout: OutputLayout,
+    concurrency: usize,
+    max_retries: u32,
+    on_outcome: F,
+) -> Vec<SceneOutcome>
+where
+    F: Fn(&SceneOutcome) + Send + Sync + 'static,
+{
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let on_outcome = Arc::new(on_outcome);
+    let names = output_names(&dirs);
+    let mut tasks = JoinSet::new();
+
+    for (index, (dir, name)) in dirs.into_iter().zip(names).enumerate() {
+        let semaphore = Arc::clone(&semaphore);
+        let on_outcome = Arc::clone(&on_outcome);
+        let cli = cli.clone();
+        let output_dir = match &output_root {
+            Some(root) => root.join(name),
+            None => dir.clone(),
+        };
+
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+            let start = Instant::now();
+            let result = run_scene(&dir, cli, output_dir, layout, max_retries)
+                .await
+                .map_err(|e| e.to_string());
+
+            let outcome = SceneOutcome {
+                input_dir: dir,
+                elapsed: start.elapsed(),
+                result,
+            };
+
+            on_outcome(&outcome);
+
+            (index, outcome)
+        });
+    }
+
+    let mut outcomes: Vec<(usize, SceneOutcome)> = tasks.join_all().await.into_iter().collect();
+    outcomes.sort_by_key(|(index, _)| *index);
+    outcomes.into_iter().map(|(_, outcome)| outcome).collect()
+}
+
+async fn run_scene(
+    dir: &Path,
+    cli: SettingsLayer,
+    output_dir: PathBuf,
+    layout: OutputLayout,
+    max_retries: u32,
+) -> Result<SceneSuccess> {
+    let mut config = DialogConfig::load(dir)?;
+    let settings = Settings::load(Some(dir), cli)?;
+    settings.apply(&mut config);
+
+    let ollama = OllamaClient::new(&settings.ollama_url.value, &settings.model.value);
+    if !ollama.health_check().await? {
+        return Err(DialogGenError::OllamaUnavailable(
+            settings.ollama_url.value.clone(),
+        ));
+    }
+
+    let orchestrator = DialogOrchestrator::new(ollama, config).with_max_retries(max_retries);
+    let dialog = orchestrator.generate().await?;
+
+    let writer = OutputWriter::with_layout(output_dir, layout, &settings.model.value)?;
+    let output_path = writer.write(&dialog)?;
+    let manifest = RunManifest::capture(&orchestrator, Some(dir)).await?;
+    writer.write_manifest(&output_path, &manifest)?;
+
+    let meta = &dialog.metadata;
+    Ok(SceneSuccess {
+        output_path,
+        model: meta.model.clone(),
+        turns: meta.turns,
+        total_tokens: meta.total_prompt_tokens + meta.total_completion_tokens,
+        tokens_per_second: meta.avg_tokens_per_second,
+        latest_link_error: writer.latest_link_error().map(str::to_string),
+    })
+}
+
+/// Format outcomes as a plain-text table
+pub fn format_summary(outcomes: &[SceneOutcome]) -> String {
+    let succeeded = outcomes.iter().filter(|o| o.result.is_ok()).count();
+    let mut summary = format!(
+        "Scenes: {}\nSucceeded: {}\nFailed: {}\n\n",
+        outcomes.len(),
+        succeeded,
+        outcomes.len() - succeeded
+    );
+
+    let _ = writeln!(
+        summary,
+        "{:<8} {:<32} {:<20} {:>5} {:>7} {:>6} {:>8}  Output / Error",
+        "Status", "Scene", "Model", "Turns", "Tokens", "Tok/s", "Time"
+    );
+
+    for outcome in outcomes {
+        let scene = outcome.input_dir.display().to_string();
+        let elapsed = format!("{:.1}s", outcome.elapsed.as_secs_f64());
+        let _ = match &outcome.result {
+            Ok(s) => writeln!(
+                summary,
+                "{:<8} {:<32} {:<20} {:>5} {:>7} {:>6.1} {:>8}  {}",
+                "ok",
+                scene,
+                s.model,
+                s.turns,
+                s.total_tokens,
+                s.tokens_per_second,
+                elapsed,
+                s.output_path.display()
+            ),
+            Err(e) => writeln!(
+                summary,
+                "{:<8} {:<32} {:<20} {:>5} {:>7} {:>6} {:>8}  {}",
+                "FAILED", scene, "-", "-", "-", "-", elapsed, e
+            ),
+        };
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn output_names_use_shortest_unique_suffix() {
+        let dirs = paths(&[
+            "/no-such-dir/a/scene1",
+            "/no-such-dir/b/scene1",
+            "/no-such-dir/a/scene2",
+        ]);
+
+        assert_eq!(
+            output_names(&dirs),
+            paths(&["a/scene1", "b/scene1", "scene2"])
+        );
+    }
+
+    #[test]
+    fn dedupe_keeps_first_listing() {
+        let dir = std::env::temp_dir();
+        let dirs = vec![dir.clone(), dir.join("."), PathBuf::from("/no-such-dir/x")];
+
+        assert_eq!(dedupe(dirs), vec![dir, PathBuf::from("/no-such-dir/x")]);
+    }
+}
@@ -0,0 +1,185 @@
+//! Character library
+//!
+//! Characters who appear in many scenes can live in a library instead of
+//! being copied into every scene's speaker files. A library is a directory
+//! of `<id>.txt` profiles in the speaker-file format. A speaker file picks
+//! one with `Character: <id>`; any other fields it sets override the
+//! profile for that scene:
+//!
+//! ```text
+//! Character: maya
+//! Motivations: Wants to convince Dev to finish the trilogy.
+//! ```
+//!
+//! Libraries are searched in order: the scene's own `characters/`, the
+//! project's `characters/` next to the scene directory, then the user's
+//! `$XDG_DATA_HOME/dialog-gen/characters/` (`~/.local/share/...`).
+
+use crate::config::DialogConfig;
+use crate::error::Result;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory name of a scene or project character library
+pub const CHARACTERS_DIR: &str = "characters";
+
+/// Speaker files that may refer to a character
+const SPEAKER_FILES: &[&str] = &["speaker1.txt", "speaker2.txt"];
+
+/// Libraries searched for a scene, in order of precedence
+pub fn search_dirs(scene_dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![scene_dir.join(CHARACTERS_DIR)];
+    if let Some(project) = scene_dir.parent() {
+        dirs.push(project.join(CHARACTERS_DIR));
+    }
+    if let Some(user) = user_library_dir() {
+        dirs.push(user);
+    }
+    dirs
+}
+
+/// `$XDG_DATA_HOME/dialog-gen/characters`, falling back to
+/// `~/.local/share/dialog-gen/characters`
+pub fn user_library_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_DATA_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+        })?;
+    Some(base.join("dialog-gen").join(CHARACTERS_DIR))
+}
+
+/// First `<id>.txt` in `dirs`. Ids are limited to letters, digits, `-` and
+/// `_`, so a reference can't point outside the libraries.
+pub fn find(id: &str, dirs: &[PathBuf]) -> Option<PathBuf> {
+    let valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return None;
+    }
+
+    dirs.iter()
+        .map(|dir| dir.join(format!("{}.txt", id)))
+        .find(|path| path.is_file())
+}
+
+/// Library files the scene's speaker files refer to
+pub fn referenced(scene_dir: &Path) -> Result<Vec<PathBuf>> {
+    let dirs = search_dirs(scene_dir);
+    let mut files = Vec::new();
+
+    for name in SPEAKER_FILES {
+        let Ok(content) = fs::read_to_string(scene_dir.join(name)) else {
+            continue;
+        };
+        if let Some(id) = DialogConfig::parse_key_value(&content).get("character")
+            && let Some(path) = find(id, &dirs)
+        {
+            files.push(path.canonicalize()?);
+        }
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    /// A scene inside a project, with a character library at each level
+    struct Project(PathBuf);
+
+    impl Project {
+        fn new(name: &str) -> Self {
+            let root = env::temp_dir().join(format!("dialog-gen-test-{}-{}", process::id(), name));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(root.join("scene").join(CHARACTERS_DIR)).unwrap();
+            fs::create_dir_all(root.join(CHARACTERS_DIR)).unwrap();
+            Self(root)
+        }
+
+        fn scene(&self) -> PathBuf {
+            self.0.join("scene")
+        }
+
+        fn dirs(&self) -> Vec<PathBuf> {
+            vec![
+                self.scene().join(CHARACTERS_DIR),
+                self.0.join(CHARACTERS_DIR),
+            ]
+        }
+    }
+
+    impl Drop for Project {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn scene_library_takes_precedence_over_project_library() {
+        let project = Project::new("characters-precedence");
+        fs::write(project.0.join("characters/maya.txt"), "Name: Maya").unwrap();
+        fs::write(project.0.join("characters/dev.txt"), "Name: Dev").unwrap();
+        fs::write(project.scene().join("characters/maya.txt"), "Name: Maya").unwrap();
+
+        let dirs = project.dirs();
+        assert_eq!(
+            find("maya", &dirs),
+            Some(project.scene().join("characters/maya.txt"))
+        );
+        assert_eq!(
+            find("dev", &dirs),
+            Some(project.0.join("characters/dev.txt"))
+        );
+        assert_eq!(find("kai", &dirs), None);
+    }
+
+    #[test]
+    fn ids_cannot_leave_the_libraries() {
+        let project = Project::new("characters-ids");
+        fs::write(project.0.join("secret.txt"), "Name: Secret").unwrap();
+        fs::write(project.0.join("characters/old-maya_2.txt"), "Name: Maya").unwrap();
+
+        let dirs = project.dirs();
+        assert!(find("old-maya_2", &dirs).is_some());
+        for id in [
+            "",
+            "../secret",
+            "..",
+            "characters/../secret",
+            "maya.txt",
+            "/etc/passwd",
+        ] {
+            assert_eq!(find(id, &dirs), None, "id {:?}", id);
+        }
+    }
+
+    #[test]
+    fn referenced_lists_the_profiles_speaker_files_use() {
+        let project = Project::new("characters-referenced");
+        let scene = project.scene();
+        fs::write(project.0.join("characters/maya.txt"), "Name: Maya").unwrap();
+        fs::write(
+            scene.join("speaker1.txt"),
+            "Character: maya\nMotivations: Win.",
+        )
+        .unwrap();
+        fs::write(scene.join("speaker2.txt"), "Name: Dev").unwrap();
+
+        let files = referenced(&scene).unwrap();
+        assert_eq!(
+            files,
+            [project
+                .0
+                .join("characters/maya.txt")
+                .canonicalize()
+                .unwrap()]
+        );
+    }
+}
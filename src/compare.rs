@@ -0,0 +1,255 @@
+//! Multi-model comparison runs for a single scene
+//!
+//! The scene is generated once per model, temperature and seed combination.
+//! Each run is written to its own labelled subdirectory, and a Markdown
+//! report puts the runs' stats and dialogs side by side.
+
+use crate::config::DialogConfig;
+use crate::error::Result;
+use crate::events::GenerationObserver;
+use crate::manifest::RunManifest;
+use crate::ollama::OllamaClient;
+use crate::orchestrator::{DialogOrchestrator, GeneratedDialog};
+use crate::output::{OutputWriter, run_model_label};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// One model/temperature/seed combination
+#[derive(Debug, Clone)]
+pub struct RunSpec {
+    pub model: String,
+    pub temperature: f32,
+    pub seed: Option<u64>,
+}
+
+impl RunSpec {
+    /// Directory-safe label, e.g. "llama3.2_t0.9_s42", naming the model
+    /// the same way run directories do
+    pub fn label(&self) -> String {
+        let model = run_model_label(&self.model);
+        match self.seed {
+            Some(seed) => format!("{}_t{}_s{}", model, self.temperature, seed),
+            None => format!("{}_t{}", model, self.temperature),
+        }
+    }
+}
+
+/// Every combination of the given models, temperatures and seeds
+pub fn expand(models: &[String], temperatures: &[f32], seeds: &[Option<u64>]) -> Vec<RunSpec> {
+    let mut specs = Vec::new();
+    for model in models {
+        for &temperature in temperatures {
+            for &seed in seeds {
+                specs.push(RunSpec {
+                    model: model.clone(),
+                    temperature,
+                    seed,
+                });
+            }
+        }
+    }
+    specs
+}
+
+/// Result of one comparison run
+#[derive(Debug)]
+pub struct RunOutcome {
+    pub spec: RunSpec,
+    pub result: std::result::Result<(GeneratedDialog, PathBuf), String>,
+}
+
+/// Run each spec in turn against the same scene, reporting each run's
+/// progress to `observer` and calling `on_outcome` as each one finishes.
+/// Runs are sequential so tokens/sec figures aren't skewed by runs competing
+/// for the same server.
+pub async fn run<F>(
+    config: &DialogConfig,
+    input_dir: &Path,
+    ollama_url: &str,
+    specs: Vec<RunSpec>,
+    output_root: &Path,
+    observer: Option<Arc<dyn GenerationObserver>>,
+    on_outcome: F,
+) -> Vec<RunOutcome>
+where
+    F: Fn(&RunOutcome),
+{
+    let mut outcomes = Vec::new();
+
+    for spec in specs {
+        let result = run_one(
+            config,
+            input_dir,
+            ollama_url,
+            &spec,
+            output_root,
+            observer.clone(),
+        )
+        .await
+        .map_err(|e| e.to_string());
+
+        let outcome = RunOutcome { spec, result };
+        on_outcome(&outcome);
+        outcomes.push(outcome);
+    }
+
+    outcomes
+}
+
+async fn run_one(
+    config: &DialogConfig,
+    input_dir: &Path,
+    ollama_url: &str,
+    spec: &RunSpec,
+    output_root: &Path,
+    observer: Option<Arc<dyn GenerationObserver>>,
+) -> Result<(GeneratedDialog, PathBuf)> {
+    let mut config = config.clone();
+    config.scene.model = Some(spec.model.clone());
+    config.scene.temperature = spec.temperature;
+    config.scene.seed = spec.seed;
+
+    let ollama = OllamaClient::new(ollama_url, &spec.model);
+    let mut orchestrator = DialogOrchestrator::new(ollama, config);
+    if let Some(observer) = observer {
+        orchestrator = orchestrator.with_observer(observer);
+    }
+    let dialog = orchestrator.generate().await?;
+
+    let writer = OutputWriter::new(output_root.join(spec.label()));
+    let path = writer.write(&dialog)?;
+    let manifest = RunManifest::capture(&orchestrator, Some(input_dir)).await?;
+    writer.write_manifest(&path, &manifest)?;
+    Ok((dialog, path))
+}
+
+/// Average words per generated (non-seed) line
+fn avg_line_words(dialog: &GeneratedDialog) -> f64 {
+    let lines: Vec<usize> = dialog
+        .exchanges
+        .iter()
+        .filter(|e| e.stats.is_some())
+        .map(|e| e.content.split_whitespace().count())
+        .collect();
+
+    if lines.is_empty() {
+        0.0
+    } else {
+        lines.iter().sum::<usize>() as f64 / lines.len() as f64
+    }
+}
+
+/// Markdown report with a stats table and the dialogs in parallel columns
+pub fn format_report(config: &DialogConfig, outcomes: &[RunOutcome]) -> String {
+    let mut report = format!(
+        "# Comparison: {}\n\n{} vs {}, {} turns\n\n",
+        config.directions.scene_name,
+        config.speaker1.name,
+        config.speaker2.name,
+        config.scene.turns
+    );
+
+    report.push_str("| Run | Model | Temp | Seed | Tokens/s | Total tokens | Avg words/line | Wall time | Output |\n");
+    report.push_str("|-----|-------|------|------|----------|--------------|----------------|-----------|--------|\n");
+
+    for outcome in outcomes {
+        let spec = &outcome.spec;
+        let seed = spec.seed.map_or("-".to_string(), |s| s.to_string());
+        let _ = match &outcome.result {
+            Ok((dialog, path)) => {
+                let meta = &dialog.metadata;
+                writeln!(
+                    report,
+                    "| {} | {} | {:.2} | {} | {:.1} | {} | {:.1} | {:.2}s | {} |",
+                    spec.label(),
+                    spec.model,
+                    spec.temperature,
+                    seed,
+                    meta.avg_tokens_per_second,
+                    meta.total_prompt_tokens + meta.total_completion_tokens,
+                    avg_line_words(dialog),
+                    meta.total_wall_time.as_secs_f64(),
+                    path.display()
+                )
+            }
+            Err(e) => writeln!(
+                report,
+                "| {} | {} | {:.2} | {} | - | - | - | - | failed: {} |",
+                spec.label(),
+                spec.model,
+                spec.temperature,
+                seed,
+                table_cell(e)
+            ),
+        };
+    }
+
+    let dialogs: Vec<(String, &GeneratedDialog)> = outcomes
+        .iter()
+        .filter_map(|o| o.result.as_ref().ok().map(|(d, _)| (o.spec.label(), d)))
+        .collect();
+
+    if dialogs.is_empty() {
+        return report;
+    }
+
+    report.push_str("\n## Dialogs\n\n| # |");
+    for (label, _) in &dialogs {
+        let _ = write!(report, " {} |", label);
+    }
+    report.push_str("\n|---|");
+    for _ in &dialogs {
+        report.push_str("---|");
+    }
+    report.push('\n');
+
+    let rows = dialogs
+        .iter()
+        .map(|(_, d)| d.exchanges.len())
+        .max()
+        .unwrap_or(0);
+    for row in 0..rows {
+        let _ = write!(report, "| {} |", row + 1);
+        for (_, dialog) in &dialogs {
+            match dialog.exchanges.get(row) {
+                Some(e) => {
+                    let _ = write!(report, " **{}:** {} |", e.speaker, table_cell(&e.content));
+                }
+                None => report.push_str("  |"),
+            }
+        }
+        report.push('\n');
+    }
+
+    report
+}
+
+/// Keep text from breaking a Markdown table row
+fn table_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_name_models_like_run_directories() {
+        let spec = |model: &str, seed| RunSpec {
+            model: model.to_string(),
+            temperature: 0.9,
+            seed,
+        };
+
+        assert_eq!(
+            spec("llama3.2:latest", Some(42)).label(),
+            "llama3.2_t0.9_s42"
+        );
+        assert_eq!(spec("mistral:7b", None).label(), "mistral-7b_t0.9");
+        assert_eq!(
+            spec("hf.co/org/my_model-v2:Q4", None).label(),
+            "hf.co-org-my_model-v2-Q4_t0.9"
+        );
+    }
+}
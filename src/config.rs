@@ -1,30 +1,41 @@
+use crate::characters;
 use crate::error::{DialogGenError, Result};
+use crate::formats::fountain;
+use crate::settings::{DEFAULT_TEMPERATURE, DEFAULT_TURNS};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
 /// Speaker character definition
-#[derive(Debug, Clone)]
-#[allow(dead_code)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Speaker {
     pub name: String,
     pub background: String,
     pub personality: String,
     pub motivations: String,
     pub speaking_style: String,
+    /// TTS voice name used by the SSML exporter
+    pub voice: Option<String>,
+    /// Words per minute used to estimate subtitle timings
+    pub speaking_rate: Option<f32>,
 }
 
 /// Scene configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Scene {
     pub turns: usize,
     pub model: Option<String>,
     pub temperature: f32,
+    /// Sampling seed for reproducible runs
+    pub seed: Option<u64>,
 }
 
 /// Director's notes
-#[derive(Debug, Clone)]
-#[allow(dead_code)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Directions {
     pub scene_name: String,
     pub setting: String,
@@ -34,14 +45,190 @@ pub struct Directions {
 }
 
 /// Parsed dialog line from prompt
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DialogLine {
     pub speaker: String,
     pub content: String,
 }
 
+impl Speaker {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_background(mut self, background: impl Into<String>) -> Self {
+        self.background = background.into();
+        self
+    }
+
+    pub fn with_personality(mut self, personality: impl Into<String>) -> Self {
+        self.personality = personality.into();
+        self
+    }
+
+    pub fn with_motivations(mut self, motivations: impl Into<String>) -> Self {
+        self.motivations = motivations.into();
+        self
+    }
+
+    pub fn with_speaking_style(mut self, speaking_style: impl Into<String>) -> Self {
+        self.speaking_style = speaking_style.into();
+        self
+    }
+
+    pub fn with_voice(mut self, voice: impl Into<String>) -> Self {
+        self.voice = Some(voice.into());
+        self
+    }
+
+    pub fn with_speaking_rate(mut self, words_per_minute: f32) -> Self {
+        self.speaking_rate = Some(words_per_minute);
+        self
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self {
+            turns: DEFAULT_TURNS,
+            model: None,
+            temperature: DEFAULT_TEMPERATURE,
+            seed: None,
+        }
+    }
+}
+
+impl Directions {
+    pub fn new(scene_name: impl Into<String>) -> Self {
+        Self {
+            scene_name: scene_name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_setting(mut self, setting: impl Into<String>) -> Self {
+        self.setting = setting.into();
+        self
+    }
+
+    pub fn with_mood(mut self, mood: impl Into<String>) -> Self {
+        self.mood = mood.into();
+        self
+    }
+
+    pub fn with_goal(mut self, goal: impl Into<String>) -> Self {
+        self.goal = goal.into();
+        self
+    }
+
+    pub fn with_notes(mut self, notes: impl Into<String>) -> Self {
+        self.notes = notes.into();
+        self
+    }
+}
+
+impl DialogLine {
+    pub fn new(speaker: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            speaker: speaker.into(),
+            content: content.into(),
+        }
+    }
+}
+
+/// Builds a [`DialogConfig`] in code instead of loading it from a directory
+#[derive(Debug, Default)]
+pub struct DialogConfigBuilder {
+    speaker1: Option<Speaker>,
+    speaker2: Option<Speaker>,
+    directions: Directions,
+    initial_lines: Vec<DialogLine>,
+    scene: Scene,
+}
+
+impl DialogConfigBuilder {
+    pub fn speaker1(mut self, speaker: Speaker) -> Self {
+        self.speaker1 = Some(speaker);
+        self
+    }
+
+    pub fn speaker2(mut self, speaker: Speaker) -> Self {
+        self.speaker2 = Some(speaker);
+        self
+    }
+
+    pub fn directions(mut self, directions: Directions) -> Self {
+        self.directions = directions;
+        self
+    }
+
+    /// Add a seed line, as one line of prompt.txt would
+    pub fn line(mut self, speaker: impl Into<String>, content: impl Into<String>) -> Self {
+        self.initial_lines.push(DialogLine::new(speaker, content));
+        self
+    }
+
+    pub fn scene(mut self, scene: Scene) -> Self {
+        self.scene = scene;
+        self
+    }
+
+    pub fn turns(mut self, turns: usize) -> Self {
+        self.scene.turns = turns;
+        self
+    }
+
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.scene.model = Some(model.into());
+        self
+    }
+
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.scene.temperature = temperature;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.scene.seed = Some(seed);
+        self
+    }
+
+    /// Validate and build. Both speakers need distinct, non-empty names.
+    pub fn build(self) -> Result<DialogConfig> {
+        let speaker1 = self
+            .speaker1
+            .ok_or_else(|| DialogGenError::InvalidConfig("speaker1 is required".to_string()))?;
+        let speaker2 = self
+            .speaker2
+            .ok_or_else(|| DialogGenError::InvalidConfig("speaker2 is required".to_string()))?;
+
+        if speaker1.name.trim().is_empty() || speaker2.name.trim().is_empty() {
+            return Err(DialogGenError::InvalidConfig(
+                "speakers need a name".to_string(),
+            ));
+        }
+        if speaker1.name == speaker2.name {
+            return Err(DialogGenError::InvalidConfig(format!(
+                "both speakers are named {}",
+                speaker1.name
+            )));
+        }
+
+        Ok(DialogConfig {
+            speaker1,
+            speaker2,
+            directions: self.directions,
+            initial_lines: self.initial_lines,
+            scene: self.scene,
+        })
+    }
+}
+
 /// Complete configuration for a dialog generation run
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DialogConfig {
     pub speaker1: Speaker,
     pub speaker2: Speaker,
@@ -51,11 +238,30 @@ pub struct DialogConfig {
 }
 
 impl DialogConfig {
+    /// Start building a scene in code
+    pub fn builder() -> DialogConfigBuilder {
+        DialogConfigBuilder::default()
+    }
+
     pub fn load(input_dir: &Path) -> Result<Self> {
-        let speaker1 = Self::load_speaker(&input_dir.join("speaker1.txt"), "speaker1.txt")?;
-        let speaker2 = Self::load_speaker(&input_dir.join("speaker2.txt"), "speaker2.txt")?;
+        let speaker1 = Self::load_speaker(input_dir, "speaker1.txt")?;
+        let speaker2 = Self::load_speaker(input_dir, "speaker2.txt")?;
         let directions = Self::load_directions(&input_dir.join("directions.txt"))?;
-        let initial_lines = Self::load_prompt(&input_dir.join("prompt.txt"))?;
+        // A Fountain script can seed the dialog in place of prompt.txt
+        let fountain_path = input_dir.join("prompt.fountain");
+        let prompt_path = input_dir.join("prompt.txt");
+        if fountain_path.exists() && prompt_path.exists() {
+            return Err(DialogGenError::InvalidConfig(format!(
+                "{} has both prompt.fountain and prompt.txt; keep only one",
+                input_dir.display()
+            )));
+        }
+        let initial_lines = if fountain_path.exists() {
+            let content = fs::read_to_string(&fountain_path)?;
+            fountain::parse(&content, &[speaker1.clone(), speaker2.clone()])?
+        } else {
+            Self::load_prompt(&prompt_path)?
+        };
         let scene = Self::load_scene(&input_dir.join("scene.txt"))?;
 
         Ok(DialogConfig {
@@ -67,11 +273,30 @@ impl DialogConfig {
         })
     }
 
-    fn load_speaker(path: &Path, filename: &str) -> Result<Speaker> {
-        let content = fs::read_to_string(path)
+    fn load_speaker(input_dir: &Path, filename: &str) -> Result<Speaker> {
+        let content = fs::read_to_string(input_dir.join(filename))
             .map_err(|_| DialogGenError::MissingFile(filename.to_string()))?;
 
-        let fields = Self::parse_key_value(&content);
+        let mut fields = Self::parse_key_value(&content);
+
+        // A `Character:` line starts from a library profile; the speaker
+        // file's own fields override it
+        if let Some(id) = fields.remove("character").filter(|id| !id.is_empty()) {
+            let dirs = characters::search_dirs(input_dir);
+            let path = characters::find(&id, &dirs).ok_or_else(|| {
+                let searched: Vec<String> = dirs.iter().map(|d| d.display().to_string()).collect();
+                DialogGenError::InvalidConfig(format!(
+                    "{}: character '{}' not found in {}",
+                    filename,
+                    id,
+                    searched.join(", ")
+                ))
+            })?;
+
+            let mut profile = Self::parse_key_value(&fs::read_to_string(&path)?);
+            profile.extend(fields.into_iter().filter(|(_, value)| !value.is_empty()));
+            fields = profile;
+        }
 
         Ok(Speaker {
             name: fields.get("name").cloned().unwrap_or_default(),
@@ -79,6 +304,8 @@ impl DialogConfig {
             personality: fields.get("personality").cloned().unwrap_or_default(),
             motivations: fields.get("motivations").cloned().unwrap_or_default(),
             speaking_style: fields.get("speaking style").cloned().unwrap_or_default(),
+            voice: fields.get("voice").filter(|v| !v.is_empty()).cloned(),
+            speaking_rate: fields.get("speaking rate").and_then(|s| s.parse().ok()),
         })
     }
 
@@ -106,19 +333,21 @@ impl DialogConfig {
         let turns = fields
             .get("turns")
             .and_then(|s| s.parse().ok())
-            .unwrap_or(4);
+            .unwrap_or(DEFAULT_TURNS);
 
         let temperature = fields
             .get("temperature")
             .and_then(|s| s.parse().ok())
-            .unwrap_or(0.7);
+            .unwrap_or(DEFAULT_TEMPERATURE);
 
         let model = fields.get("model").cloned();
+        let seed = fields.get("seed").and_then(|s| s.parse().ok());
 
         Ok(Scene {
             turns,
             model,
             temperature,
+            seed,
         })
     }
 
@@ -161,7 +390,7 @@ impl DialogConfig {
 
     /// Parse key-value pairs from a file with format "Key: Value"
     /// Handles multi-line values (lines without colons are appended to previous value)
-    fn parse_key_value(content: &str) -> HashMap<String, String> {
+    pub(crate) fn parse_key_value(content: &str) -> HashMap<String, String> {
         let mut fields = HashMap::new();
         let mut current_key: Option<String> = None;
         let mut current_value = String::new();
@@ -206,3 +435,61 @@ impl DialogConfig {
         fields
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    /// A scene directory with Maya and Dev and the given seed script
+    fn scene(name: &str, fountain: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("dialog-gen-test-{}-{}", process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("speaker1.txt"), "Name: Maya").unwrap();
+        fs::write(dir.join("speaker2.txt"), "Name: Dev").unwrap();
+        fs::write(dir.join("directions.txt"), "Scene: Book Club").unwrap();
+        fs::write(dir.join("scene.txt"), "Turns: 2").unwrap();
+        fs::write(dir.join("prompt.fountain"), fountain).unwrap();
+        dir
+    }
+
+    #[test]
+    fn fountain_seed_lines_use_the_speaker_names() {
+        let dir = scene(
+            "config-fountain",
+            "MAYA\nYou finished it?\n\nDEV\nLast night.\n",
+        );
+        let config = DialogConfig::load(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let lines: Vec<(String, String)> = config
+            .unwrap()
+            .initial_lines
+            .into_iter()
+            .map(|line| (line.speaker, line.content))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                ("Maya".to_string(), "You finished it?".to_string()),
+                ("Dev".to_string(), "Last night.".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn fountain_cues_for_other_characters_are_rejected() {
+        let dir = scene(
+            "config-fountain-unknown",
+            "MAYA\nHello.\n\nNARRATOR\nMeanwhile.\n",
+        );
+        let result = DialogConfig::load(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        match result {
+            Err(DialogGenError::InvalidConfig(message)) => assert!(message.contains("'NARRATOR'")),
+            other => panic!("expected InvalidConfig, got {:?}", other.map(|_| ())),
+        }
+    }
+}
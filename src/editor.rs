@@ -1,6 +1,16 @@
+use crate::analysis;
+use crate::backend::ChatBackend;
+use crate::config::Speaker;
+use crate::error::DialogGenError;
 use crate::error::Result;
-use crate::ollama::{ChatMessage, OllamaClient};
+use crate::events::{GenerationEvent, GenerationObserver};
+use crate::ollama::{ChatMessage, ChatOptions, ChatResult, ChatStats, OllamaClient, extract_json};
 use crate::orchestrator::{DialogExchange, GeneratedDialog, GenerationMetadata};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
 
 const PODCAST_EDITOR_SYSTEM_PROMPT: &str = r#"You are an expert podcast editor with years of experience making conversations more engaging and natural-sounding.
 
@@ -13,27 +23,276 @@ Your goal is to edit podcast scripts so that:
 - Repetitive content is trimmed or varied
 - The energy and momentum build appropriately
 
-Edit the following podcast script. Preserve the speaker names and format (SPEAKER: dialog).
-Make it tighter, more engaging, and more natural. Keep the same general content and meaning, but improve the delivery."#;
+Edit the following podcast script. Make it tighter, more engaging, and more natural. Keep the same general content and meaning, but improve the delivery."#;
 
-pub struct PodcastEditor {
-    ollama: OllamaClient,
+const LINE_EDITOR_SYSTEM_PROMPT: &str = r#"You are an expert podcast editor with years of experience making conversations more engaging and natural-sounding.
+
+Revise the script so that:
+- The conversation flows naturally with good pacing
+- Each speaker has a distinct, consistent voice
+- Awkward phrasings are smoothed out
+- Repetitive content is trimmed or varied"#;
+
+/// Reply format appended to the system prompt in script mode
+const SCRIPT_EDIT_FORMAT: &str = r#"Preserve the speaker names and format. Reply with the edited script only, one line per speaker turn, each written as SPEAKER: dialog, with a blank line between turns."#;
+
+/// Reply format appended to the system prompt in line mode
+const LINE_EDIT_FORMAT: &str = r#"You will receive a podcast script with numbered lines. For every line, choose one action:
+- "keep": leave the line as it is
+- "rewrite": replace the line's text, same speaker
+- "delete": remove the line
+- "insert_after": add a new line after it (give "speaker" and "text")
+
+Reply with JSON only, in this shape:
+{"edits": [{"line": 1, "action": "keep"}, {"line": 2, "action": "rewrite", "text": "New wording."}, {"line": 3, "action": "insert_after", "speaker": "NAME", "text": "A new line."}]}
+
+Use "line": 0 with "insert_after" to add a line before line 1. An inserted line's "speaker" must be one of the script's speakers.
+Lines you don't mention are kept unchanged. Never merge lines or change who says a line."#;
+
+/// Name recorded for the built-in single editing pass
+pub const DEFAULT_PASS_NAME: &str = "podcast-editor";
+
+/// Temperature for editing passes that don't set one
+pub const DEFAULT_EDIT_TEMPERATURE: f32 = 0.7;
+
+/// One editing pass applied to a dialog, recorded in its metadata
+#[derive(Debug, Clone)]
+pub struct EditPass {
+    pub name: String,
+    pub mode: EditMode,
+    pub temperature: f32,
+    /// Stats for the editor call, including the model used
+    pub stats: ChatStats,
+    /// Line edits the editor asked for that could not be applied
+    pub dropped_edits: Vec<String>,
 }
 
-impl PodcastEditor {
-    pub fn new(ollama: OllamaClient) -> Self {
-        Self { ollama }
+/// How the editor revises a dialog
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum EditMode {
+    /// Rewrite the whole script and parse whatever comes back
+    #[default]
+    Script,
+    /// Ask for keep/rewrite/delete/insert-after revisions per numbered line
+    /// and apply them to the original lines
+    Lines,
+}
+
+impl EditMode {
+    /// Every edit mode, in the order `--help` lists them
+    pub const ALL: &[EditMode] = &[EditMode::Script, EditMode::Lines];
+
+    /// Name used on the command line and in scene files
+    pub fn name(self) -> &'static str {
+        match self {
+            EditMode::Script => "script",
+            EditMode::Lines => "lines",
+        }
+    }
+}
+
+impl fmt::Display for EditMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
     }
+}
 
-    /// Edit a generated dialog to improve quality
-    pub async fn edit(&self, dialog: &GeneratedDialog, verbose: bool) -> Result<GeneratedDialog> {
-        if verbose {
-            eprintln!("\n--- Editing podcast script ---\n");
+impl FromStr for EditMode {
+    type Err = DialogGenError;
+
+    /// Parse a name, ignoring case
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|v| v.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| DialogGenError::InvalidConfig(format!("unknown edit mode '{}'", s)))
+    }
+}
+
+/// One revision requested by the line editor
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LineEdit {
+    /// 1-based line number in the numbered script
+    pub line: usize,
+    pub action: LineAction,
+    #[serde(default)]
+    pub text: Option<String>,
+    /// Speaker of an inserted line
+    #[serde(default)]
+    pub speaker: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineAction {
+    Keep,
+    Rewrite,
+    Delete,
+    #[serde(alias = "insert-after", alias = "insert")]
+    InsertAfter,
+}
+
+impl LineAction {
+    /// Name used in the reply format
+    pub fn name(self) -> &'static str {
+        match self {
+            LineAction::Keep => "keep",
+            LineAction::Rewrite => "rewrite",
+            LineAction::Delete => "delete",
+            LineAction::InsertAfter => "insert_after",
+        }
+    }
+}
+
+/// The editor's reply: either `{"edits": [...]}` or a bare array
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LineEditReply {
+    Wrapped { edits: Vec<LineEdit> },
+    Bare(Vec<LineEdit>),
+}
+
+/// An editor reply applied to a dialog
+struct Revision {
+    exchanges: Vec<DialogExchange>,
+    /// Lines other than the dialog's seed lines, counted like
+    /// `GenerationMetadata::turns`
+    turns: usize,
+    stats: ChatStats,
+    dropped_edits: Vec<String>,
+}
+
+pub struct PodcastEditor<B = OllamaClient> {
+    backend: B,
+    mode: EditMode,
+    name: String,
+    system_prompt: Option<String>,
+    temperature: f32,
+    speakers: Vec<Speaker>,
+    observer: Option<Arc<dyn GenerationObserver>>,
+}
+
+impl<B: ChatBackend> PodcastEditor<B> {
+    pub fn new(backend: B) -> Self {
+        Self {
+            backend,
+            mode: EditMode::default(),
+            name: DEFAULT_PASS_NAME.to_string(),
+            system_prompt: None,
+            temperature: DEFAULT_EDIT_TEMPERATURE,
+            speakers: Vec::new(),
+            observer: None,
+        }
+    }
+
+    /// Speaker profiles used for style cue metrics on the edited dialog
+    pub fn with_speakers(mut self, speakers: Vec<Speaker>) -> Self {
+        self.speakers = speakers;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: EditMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Name this pass is recorded under in metadata
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Replace the built-in editor prompt. The reply format instructions for
+    /// the edit mode are still appended.
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(prompt.into());
+        self
+    }
+
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    /// Report edit progress events to `observer`
+    pub fn with_observer(mut self, observer: Arc<dyn GenerationObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    fn emit(&self, event: GenerationEvent) {
+        if let Some(observer) = &self.observer {
+            observer.on_event(event);
         }
+    }
+
+    /// Edit a generated dialog to improve quality
+    pub async fn edit(&self, dialog: &GeneratedDialog) -> Result<GeneratedDialog> {
+        self.emit(GenerationEvent::EditStarted {
+            pass: self.name.clone(),
+        });
+
+        // Seed lines lead the dialog and are the lines `turns` leaves out
+        let seeds = dialog.exchanges.len().saturating_sub(dialog.metadata.turns);
+        let Revision {
+            exchanges: edited_exchanges,
+            turns,
+            stats,
+            dropped_edits,
+        } = match self.mode {
+            EditMode::Script => self.edit_script(&dialog.exchanges, seeds).await?,
+            EditMode::Lines => self.edit_lines(&dialog.exchanges, seeds).await?,
+        };
+
+        self.emit(GenerationEvent::EditCompleted {
+            stats: stats.clone(),
+        });
+
+        let model = if dialog.metadata.edit_passes.is_empty() {
+            format!("{} (edited)", dialog.metadata.model)
+        } else {
+            dialog.metadata.model.clone()
+        };
+        let mut edit_passes = dialog.metadata.edit_passes.clone();
+        edit_passes.push(EditPass {
+            name: self.name.clone(),
+            mode: self.mode,
+            temperature: self.temperature,
+            stats: stats.clone(),
+            dropped_edits,
+        });
+
+        // Update metadata to reflect editing
+        let metadata = GenerationMetadata {
+            model,
+            turns,
+            temperature: dialog.metadata.temperature,
+            total_prompt_tokens: dialog.metadata.total_prompt_tokens + stats.prompt_tokens,
+            total_completion_tokens: dialog.metadata.total_completion_tokens
+                + stats.completion_tokens,
+            total_wall_time: dialog.metadata.total_wall_time + stats.wall_time,
+            avg_tokens_per_second: dialog.metadata.avg_tokens_per_second, // Keep original
+            analysis: Some(analysis::analyze(&edited_exchanges, &self.speakers)),
+            edit_passes,
+        };
 
+        self.emit(GenerationEvent::Finished {
+            metadata: metadata.clone(),
+        });
+
+        Ok(GeneratedDialog {
+            exchanges: edited_exchanges,
+            metadata,
+        })
+    }
+
+    /// Rewrite the whole script in one pass
+    async fn edit_script(&self, exchanges: &[DialogExchange], seeds: usize) -> Result<Revision> {
         // Format the dialog as a script
         let mut script = String::new();
-        for exchange in &dialog.exchanges {
+        for exchange in exchanges {
             script.push_str(&format!("{}: {}\n\n", exchange.speaker, exchange.content));
         }
 
@@ -44,69 +303,444 @@ impl PodcastEditor {
             content: user_prompt,
         }];
 
-        if verbose {
-            eprint!("Sending to editor... ");
-        }
-
+        let options = ChatOptions {
+            temperature: self.temperature,
+            seed: None,
+        };
+        let system_prompt = format!(
+            "{}\n\n{}",
+            self.system_prompt
+                .as_deref()
+                .unwrap_or(PODCAST_EDITOR_SYSTEM_PROMPT),
+            SCRIPT_EDIT_FORMAT
+        );
         let result = self
-            .ollama
-            .chat(PODCAST_EDITOR_SYSTEM_PROMPT, &messages, 0.7)
+            .backend
+            .chat(&system_prompt, &messages, &options)
             .await?;
 
-        if verbose {
-            eprintln!("done ({:.1}s)", result.stats.wall_time.as_secs_f64());
+        // Parse the edited script back into exchanges
+        let edited = parse_script(&result.content);
+        if edited.is_empty() && !exchanges.is_empty() {
+            return Err(DialogGenError::GenerationFailed(format!(
+                "Editor pass '{}' returned no SPEAKER: dialog lines",
+                self.name
+            )));
         }
+        // The script comes back whole, seed lines included
+        Ok(Revision {
+            turns: edited.len().saturating_sub(seeds),
+            exchanges: edited,
+            stats: result.stats,
+            dropped_edits: Vec::new(),
+        })
+    }
 
-        // Parse the edited script back into exchanges
-        let edited_exchanges = self.parse_script(&result.content);
+    /// Ask for per-line revisions and apply them to the original lines
+    async fn edit_lines(&self, exchanges: &[DialogExchange], seeds: usize) -> Result<Revision> {
+        let mut script = String::new();
+        for (i, exchange) in exchanges.iter().enumerate() {
+            script.push_str(&format!(
+                "{}. {}: {}\n",
+                i + 1,
+                exchange.speaker,
+                exchange.content
+            ));
+        }
 
-        // Update metadata to reflect editing
-        let metadata = GenerationMetadata {
-            model: format!("{} (edited)", dialog.metadata.model),
-            turns: edited_exchanges.len(),
-            temperature: dialog.metadata.temperature,
-            total_prompt_tokens: dialog.metadata.total_prompt_tokens + result.stats.prompt_tokens,
-            total_completion_tokens: dialog.metadata.total_completion_tokens
-                + result.stats.completion_tokens,
-            total_wall_time: dialog.metadata.total_wall_time + result.stats.wall_time,
-            avg_tokens_per_second: dialog.metadata.avg_tokens_per_second, // Keep original
+        let messages = vec![ChatMessage {
+            role: "user".to_string(),
+            content: format!("Revise this podcast script:\n\n{}", script.trim()),
+        }];
+
+        let options = ChatOptions {
+            temperature: self.temperature,
+            seed: None,
         };
+        let system_prompt = format!(
+            "{}\n\n{}",
+            self.system_prompt
+                .as_deref()
+                .unwrap_or(LINE_EDITOR_SYSTEM_PROMPT),
+            LINE_EDIT_FORMAT
+        );
+        let ChatResult { content, stats } = self
+            .backend
+            .chat_json(&system_prompt, &messages, &options)
+            .await?;
 
-        Ok(GeneratedDialog {
-            exchanges: edited_exchanges,
-            metadata,
+        let edits = parse_line_edits(&content)?;
+        let (edited, dropped_edits) = revise_lines(exchanges, &edits);
+        // Inserted lines are new turns; kept and rewritten seed lines aren't
+        let turns = edited
+            .iter()
+            .filter(|(source, _)| source.is_none_or(|i| i >= seeds))
+            .count();
+        Ok(Revision {
+            exchanges: edited.into_iter().map(|(_, exchange)| exchange).collect(),
+            turns,
+            stats,
+            dropped_edits,
         })
     }
+}
+
+/// Parse the line editor's JSON reply
+pub fn parse_line_edits(reply: &str) -> Result<Vec<LineEdit>> {
+    match serde_json::from_str(extract_json(reply)) {
+        Ok(LineEditReply::Wrapped { edits }) | Ok(LineEditReply::Bare(edits)) => Ok(edits),
+        Err(e) => Err(DialogGenError::GenerationFailed(format!(
+            "Editor reply is not valid line edits: {}",
+            e
+        ))),
+    }
+}
 
-    /// Parse a script back into dialog exchanges
-    fn parse_script(&self, script: &str) -> Vec<DialogExchange> {
-        let mut exchanges = Vec::new();
+/// Apply line edits to the original exchanges. Lines without an edit are
+/// kept and the last keep/rewrite/delete for a line wins. `insert_after`
+/// line 0 inserts before the first line; inserted lines without a speaker go
+/// to whoever didn't speak the line they follow.
+///
+/// Returns the edited exchanges and a description of each edit that could
+/// not be applied: edits naming lines that don't exist, rewrites and inserts
+/// without text, and inserts by a speaker who isn't in the dialog.
+pub fn apply_line_edits(
+    exchanges: &[DialogExchange],
+    edits: &[LineEdit],
+) -> (Vec<DialogExchange>, Vec<String>) {
+    let (edited, dropped) = revise_lines(exchanges, edits);
+    (
+        edited.into_iter().map(|(_, exchange)| exchange).collect(),
+        dropped,
+    )
+}
+
+/// [`apply_line_edits`], pairing each resulting line with the index of the
+/// original line it came from (None for inserted lines)
+fn revise_lines(
+    exchanges: &[DialogExchange],
+    edits: &[LineEdit],
+) -> (Vec<(Option<usize>, DialogExchange)>, Vec<String>) {
+    let mut actions: HashMap<usize, &LineEdit> = HashMap::new();
+    let mut inserts: HashMap<usize, Vec<&LineEdit>> = HashMap::new();
+    let mut dropped = Vec::new();
+    for edit in edits {
+        let first = match edit.action {
+            LineAction::InsertAfter => 0,
+            _ => 1,
+        };
+        if edit.line < first || edit.line > exchanges.len() {
+            dropped.push(format!(
+                "line {}: {} names a line that doesn't exist",
+                edit.line,
+                edit.action.name()
+            ));
+            continue;
+        }
+        match edit.action {
+            LineAction::InsertAfter => inserts.entry(edit.line).or_default().push(edit),
+            _ => {
+                actions.insert(edit.line, edit);
+            }
+        }
+    }
 
-        for line in script.lines() {
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
+    let text = |edit: &LineEdit| {
+        edit.text
+            .as_deref()
+            .map(str::trim)
+            .unwrap_or("")
+            .to_string()
+    };
+    let insert_lines = |line: usize,
+                        previous: Option<&str>,
+                        result: &mut Vec<(Option<usize>, DialogExchange)>,
+                        dropped: &mut Vec<String>| {
+        for insert in inserts.get(&line).into_iter().flatten() {
+            let content = text(insert);
+            if content.is_empty() {
+                dropped.push(format!("line {}: insert_after has no text", line));
                 continue;
             }
+            // Only the dialog's own speakers may be given new lines; match
+            // the script's spelling when the editor changes case
+            let speaker = match insert.speaker.as_deref().map(str::trim) {
+                Some(name) if !name.is_empty() => exchanges
+                    .iter()
+                    .map(|e| &e.speaker)
+                    .find(|speaker| speaker.eq_ignore_ascii_case(name))
+                    .cloned()
+                    .ok_or_else(|| name.to_string()),
+                _ => {
+                    let neighbour = previous.or(exchanges.first().map(|e| e.speaker.as_str()));
+                    Ok(exchanges
+                        .iter()
+                        .map(|e| &e.speaker)
+                        .find(|s| Some(s.as_str()) != neighbour)
+                        .or(exchanges.first().map(|e| &e.speaker))
+                        .cloned()
+                        .unwrap_or_default())
+                }
+            };
+            match speaker {
+                Ok(speaker) => result.push((
+                    None,
+                    DialogExchange {
+                        speaker,
+                        content,
+                        stats: None,
+                    },
+                )),
+                Err(name) => dropped.push(format!(
+                    "line {}: insert_after by \"{}\", who isn't in the dialog",
+                    line, name
+                )),
+            }
+        }
+    };
 
-            // Look for "Speaker: content" pattern
-            if let Some(colon_pos) = trimmed.find(':') {
-                let speaker = trimmed[..colon_pos].trim();
-                let content = trimmed[colon_pos + 1..].trim();
-
-                // Validate it looks like a speaker name (starts with uppercase, reasonable length)
-                if !speaker.is_empty()
-                    && speaker.len() < 50
-                    && speaker.chars().next().is_some_and(|c| c.is_uppercase())
-                    && !content.is_empty()
-                {
-                    exchanges.push(DialogExchange {
-                        speaker: speaker.to_string(),
-                        content: content.to_string(),
-                    });
+    let mut result = Vec::new();
+    insert_lines(0, None, &mut result, &mut dropped);
+    for (i, exchange) in exchanges.iter().enumerate() {
+        let line = i + 1;
+        match actions.get(&line) {
+            Some(edit) if edit.action == LineAction::Delete => {}
+            Some(edit) if edit.action == LineAction::Rewrite && !text(edit).is_empty() => {
+                result.push((
+                    Some(i),
+                    DialogExchange {
+                        speaker: exchange.speaker.clone(),
+                        content: text(edit),
+                        stats: None,
+                    },
+                ));
+            }
+            edit => {
+                if edit.is_some_and(|edit| edit.action == LineAction::Rewrite) {
+                    dropped.push(format!("line {}: rewrite has no text", line));
                 }
+                result.push((
+                    Some(i),
+                    DialogExchange {
+                        stats: None,
+                        ..exchange.clone()
+                    },
+                ));
             }
         }
+        insert_lines(line, Some(&exchange.speaker), &mut result, &mut dropped);
+    }
+
+    (result, dropped)
+}
 
+/// Speakers of a script in order of their first line, without profiles
+pub fn script_speakers(exchanges: &[DialogExchange]) -> Vec<Speaker> {
+    let mut speakers: Vec<Speaker> = Vec::new();
+    for exchange in exchanges {
+        if !speakers.iter().any(|s| s.name == exchange.speaker) {
+            speakers.push(Speaker::new(&exchange.speaker));
+        }
+    }
+    speakers
+}
+
+/// Parse a "SPEAKER: dialog" script into dialog exchanges
+pub fn parse_script(script: &str) -> Vec<DialogExchange> {
+    let mut exchanges = Vec::new();
+
+    for line in script.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        // Look for "Speaker: content" pattern
+        if let Some(colon_pos) = trimmed.find(':') {
+            let speaker = trimmed[..colon_pos].trim();
+            let content = trimmed[colon_pos + 1..].trim();
+
+            // Validate it looks like a speaker name (starts with uppercase, reasonable length)
+            if !speaker.is_empty()
+                && speaker.len() < 50
+                && speaker.chars().next().is_some_and(|c| c.is_uppercase())
+                && !content.is_empty()
+            {
+                exchanges.push(DialogExchange {
+                    speaker: speaker.to_string(),
+                    content: content.to_string(),
+                    stats: None,
+                });
+            }
+        }
+    }
+
+    exchanges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange(speaker: &str, content: &str) -> DialogExchange {
+        DialogExchange {
+            speaker: speaker.to_string(),
+            content: content.to_string(),
+            stats: None,
+        }
+    }
+
+    fn script() -> Vec<DialogExchange> {
+        vec![
+            exchange("Maya", "Did you finish it?"),
+            exchange("Dev", "Last night."),
+            exchange("Maya", "And?"),
+        ]
+    }
+
+    fn edit(line: usize, action: LineAction, text: Option<&str>) -> LineEdit {
+        LineEdit {
+            line,
+            action,
+            text: text.map(str::to_string),
+            speaker: None,
+        }
+    }
+
+    fn lines(exchanges: &[DialogExchange]) -> Vec<String> {
         exchanges
+            .iter()
+            .map(|e| format!("{}: {}", e.speaker, e.content))
+            .collect()
+    }
+
+    #[test]
+    fn applies_rewrite_delete_and_insert() {
+        let edits = [
+            edit(1, LineAction::Keep, None),
+            edit(2, LineAction::Rewrite, Some("  Finished it last night. ")),
+            edit(3, LineAction::Delete, None),
+            edit(3, LineAction::InsertAfter, Some("Loved it.")),
+        ];
+        let (edited, dropped) = apply_line_edits(&script(), &edits);
+
+        assert_eq!(
+            lines(&edited),
+            [
+                "Maya: Did you finish it?",
+                "Dev: Finished it last night.",
+                "Dev: Loved it."
+            ]
+        );
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn insert_after_line_zero_goes_first() {
+        let edits = [edit(0, LineAction::InsertAfter, Some("Welcome back."))];
+        let (edited, _) = apply_line_edits(&script(), &edits);
+
+        assert_eq!(edited[0].content, "Welcome back.");
+        assert_eq!(edited[0].speaker, "Dev");
+        assert_eq!(edited.len(), 4);
+    }
+
+    #[test]
+    fn insert_speaker_uses_script_spelling() {
+        let mut insert = edit(1, LineAction::InsertAfter, Some("Wait."));
+        insert.speaker = Some("MAYA".to_string());
+        let (edited, dropped) = apply_line_edits(&script(), &[insert]);
+
+        assert_eq!(edited[1].speaker, "Maya");
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn reports_edits_it_cannot_apply() {
+        let mut stranger = edit(1, LineAction::InsertAfter, Some("Hi."));
+        stranger.speaker = Some("Narrator".to_string());
+        let edits = [
+            edit(0, LineAction::Rewrite, Some("Nope.")),
+            edit(9, LineAction::Delete, None),
+            edit(2, LineAction::Rewrite, Some("   ")),
+            edit(2, LineAction::InsertAfter, None),
+            stranger,
+        ];
+        let (edited, dropped) = apply_line_edits(&script(), &edits);
+
+        assert_eq!(lines(&edited), lines(&script()));
+        assert_eq!(
+            dropped,
+            [
+                "line 0: rewrite names a line that doesn't exist",
+                "line 9: delete names a line that doesn't exist",
+                "line 1: insert_after by \"Narrator\", who isn't in the dialog",
+                "line 2: rewrite has no text",
+                "line 2: insert_after has no text",
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_wrapped_bare_and_fenced_replies() {
+        let wrapped = parse_line_edits(r#"{"edits": [{"line": 2, "action": "delete"}]}"#).unwrap();
+        assert_eq!(wrapped, [edit(2, LineAction::Delete, None)]);
+
+        let bare = parse_line_edits(
+            "Here you go:\n```json\n[{\"line\": 1, \"action\": \"insert-after\", \"text\": \"Hi.\", \"speaker\": \"Dev\"}]\n```",
+        )
+        .unwrap();
+        assert_eq!(bare[0].action, LineAction::InsertAfter);
+        assert_eq!(bare[0].speaker.as_deref(), Some("Dev"));
+
+        assert!(parse_line_edits("no edits today").is_err());
+    }
+
+    #[test]
+    fn parses_script_lines() {
+        let parsed = parse_script("Maya: Hello.\n\nnote: skipped\nDev:\nDev: Hi: there.\n");
+        assert_eq!(lines(&parsed), ["Maya: Hello.", "Dev: Hi: there."]);
+    }
+
+    /// `script()` as generated from one seed line: two counted turns
+    fn seeded_dialog() -> GeneratedDialog {
+        let mut dialog = GeneratedDialog::from_script(script(), "canned", 0.7, &[]);
+        dialog.metadata.turns = 2;
+        dialog
+    }
+
+    #[tokio::test]
+    async fn line_edits_count_only_non_seed_lines_as_turns() {
+        use crate::backend::CannedBackend;
+
+        let reply = r#"{"edits": [
+            {"line": 1, "action": "rewrite", "text": "Finished it yet?"},
+            {"line": 1, "action": "insert_after", "text": "Tell me you did."},
+            {"line": 3, "action": "delete"}
+        ]}"#;
+        let backend = CannedBackend(|_: &str, _: &[ChatMessage]| Ok(reply.to_string()));
+        let edited = PodcastEditor::new(backend)
+            .with_mode(EditMode::Lines)
+            .edit(&seeded_dialog())
+            .await
+            .unwrap();
+
+        assert_eq!(edited.exchanges.len(), 3);
+        assert_eq!(edited.metadata.turns, 2);
+    }
+
+    #[tokio::test]
+    async fn script_edits_keep_the_seed_lines_out_of_turns() {
+        use crate::backend::CannedBackend;
+
+        let reply = "Maya: Did you finish it?\n\nDev: Last night.\n\nMaya: And?\n\nDev: Loved it.";
+        let backend = CannedBackend(|_: &str, _: &[ChatMessage]| Ok(reply.to_string()));
+        let edited = PodcastEditor::new(backend)
+            .edit(&seeded_dialog())
+            .await
+            .unwrap();
+
+        assert_eq!(edited.exchanges.len(), 4);
+        assert_eq!(edited.metadata.turns, 3);
     }
 }
@@ -5,12 +5,18 @@ pub enum DialogGenError {
     #[error("Missing required file: {0}")]
     MissingFile(String),
 
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
     #[error("Ollama server unavailable at {0}")]
     OllamaUnavailable(String),
 
     #[error("LLM generation failed: {0}")]
     GenerationFailed(String),
 
+    #[error("Edit fidelity below limits: {0}")]
+    FidelityFailed(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
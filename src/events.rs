@@ -0,0 +1,118 @@
+//! Progress events emitted while generating and editing dialog
+//!
+//! The orchestrator and podcast editor report progress through a
+//! `GenerationObserver` instead of printing. A closure, a tokio channel
+//! sender or `StderrProgress` can be plugged in, so the CLI, a TUI or a
+//! server can each render progress their own way.
+
+use crate::config::DialogLine;
+use crate::ollama::ChatStats;
+use crate::orchestrator::GenerationMetadata;
+use std::io::Write;
+use tokio::sync::mpsc;
+
+/// Something that happened during a generation or edit run
+#[derive(Debug, Clone)]
+pub enum GenerationEvent {
+    /// Generation started from the given seed lines
+    Started {
+        initial_lines: Vec<DialogLine>,
+        turns: usize,
+    },
+    /// A speaker's turn is about to be requested (turn is 1-based)
+    TurnStarted {
+        turn: usize,
+        total: usize,
+        speaker: String,
+    },
+    /// A chunk of the reply for the current turn arrived
+    Token { turn: usize, text: String },
+    /// A turn failed or came back empty and is being retried
+    Retry {
+        turn: usize,
+        /// 1 for the first retry
+        retry: u32,
+        max_retries: u32,
+        error: String,
+    },
+    /// A turn finished with its cleaned line
+    TurnCompleted {
+        turn: usize,
+        speaker: String,
+        content: String,
+        stats: ChatStats,
+    },
+    /// A podcast editor pass started
+    EditStarted { pass: String },
+    /// A podcast editor pass returned
+    EditCompleted { stats: ChatStats },
+    /// The run finished
+    Finished { metadata: GenerationMetadata },
+}
+
+/// Receives progress events. Implementations must be cheap; they are
+/// called inline from the generation loop.
+pub trait GenerationObserver: Send + Sync {
+    fn on_event(&self, event: GenerationEvent);
+}
+
+impl<F> GenerationObserver for F
+where
+    F: Fn(GenerationEvent) + Send + Sync,
+{
+    fn on_event(&self, event: GenerationEvent) {
+        self(event)
+    }
+}
+
+/// Forward events to a channel; events are dropped once the receiver is gone
+impl GenerationObserver for mpsc::UnboundedSender<GenerationEvent> {
+    fn on_event(&self, event: GenerationEvent) {
+        let _ = self.send(event);
+    }
+}
+
+/// Prints human-readable progress to stderr, streaming tokens as they arrive
+#[derive(Debug, Default)]
+pub struct StderrProgress;
+
+impl GenerationObserver for StderrProgress {
+    fn on_event(&self, event: GenerationEvent) {
+        match event {
+            GenerationEvent::Started {
+                initial_lines,
+                turns,
+            } => {
+                eprintln!("Initial dialog:");
+                for line in &initial_lines {
+                    eprintln!("  {}: {}", line.speaker, line.content);
+                }
+                eprintln!("\nGenerating {} turns...\n", turns);
+            }
+            GenerationEvent::TurnStarted {
+                turn,
+                total,
+                speaker,
+            } => eprint!("Turn {}/{}: {} ... ", turn, total, speaker),
+            GenerationEvent::Token { text, .. } => {
+                eprint!("{}", text);
+                let _ = std::io::stderr().flush();
+            }
+            GenerationEvent::Retry {
+                retry,
+                max_retries,
+                error,
+                ..
+            } => eprint!(" (retry {}/{}: {}) ", retry, max_retries, error),
+            GenerationEvent::TurnCompleted { .. } => eprintln!(),
+            GenerationEvent::EditStarted { pass } => {
+                eprintln!("\n--- Editing podcast script ({}) ---\n", pass);
+                eprint!("Sending to editor... ");
+            }
+            GenerationEvent::EditCompleted { stats } => {
+                eprintln!("done ({:.1}s)", stats.wall_time.as_secs_f64())
+            }
+            GenerationEvent::Finished { .. } => {}
+        }
+    }
+}
@@ -0,0 +1,541 @@
+//! Fidelity checks for edited dialog
+//!
+//! The podcast editor can drop, merge or invent lines, and `parse_script`
+//! accepts any capitalized word before a colon as a speaker. These checks
+//! compare the edit with the original: the speaker set, the turn count and
+//! a line-by-line alignment by word similarity.
+
+use crate::analysis;
+use crate::error::{DialogGenError, Result};
+use crate::orchestrator::DialogExchange;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Limits an edit has to stay within
+#[derive(Debug, Clone)]
+pub struct FidelityOptions {
+    /// Allowed change in line count, as a fraction of the original
+    pub turn_tolerance: f64,
+    /// Word similarity (0-1) needed for an edited line to count as a
+    /// revision of an original line
+    pub min_line_similarity: f64,
+    /// Share of original lines (0-1) that must map to an edited line
+    pub threshold: f64,
+    /// What `FidelityReport::enforce` does with an edit outside the limits
+    pub on_fail: FidelityAction,
+}
+
+impl Default for FidelityOptions {
+    fn default() -> Self {
+        Self {
+            turn_tolerance: 0.25,
+            min_line_similarity: 0.3,
+            threshold: 0.8,
+            on_fail: FidelityAction::default(),
+        }
+    }
+}
+
+/// What to do when an edit fails the fidelity checks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum FidelityAction {
+    /// Print the problems and keep going
+    #[default]
+    Warn,
+    /// Exit with an error (the edited files are still written)
+    Fail,
+}
+
+impl FidelityAction {
+    /// Every fidelity action, in the order `--help` lists them
+    pub const ALL: &[FidelityAction] = &[FidelityAction::Warn, FidelityAction::Fail];
+
+    /// Name used on the command line and in scene files
+    pub fn name(self) -> &'static str {
+        match self {
+            FidelityAction::Warn => "warn",
+            FidelityAction::Fail => "fail",
+        }
+    }
+}
+
+impl fmt::Display for FidelityAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for FidelityAction {
+    type Err = DialogGenError;
+
+    /// Parse a name, ignoring case
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|v| v.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                DialogGenError::InvalidConfig(format!("unknown fidelity action '{}'", s))
+            })
+    }
+}
+
+/// How one original line fared in the edit
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineChange {
+    Unchanged {
+        original: usize,
+        edited: usize,
+    },
+    Revised {
+        original: usize,
+        edited: usize,
+        similarity: f64,
+    },
+    Dropped {
+        original: usize,
+    },
+    Added {
+        edited: usize,
+    },
+}
+
+/// Result of comparing an edited dialog with its original
+#[derive(Debug, Clone)]
+pub struct FidelityReport {
+    pub original: Vec<DialogExchange>,
+    pub edited: Vec<DialogExchange>,
+    /// Speakers in the edit that aren't in the original
+    pub invented_speakers: Vec<String>,
+    /// Speakers in the original that the edit lost
+    pub missing_speakers: Vec<String>,
+    /// Alignment in script order
+    pub changes: Vec<LineChange>,
+    /// Share of original lines that map to an edited line
+    pub score: f64,
+    pub options: FidelityOptions,
+}
+
+/// Compare an edited dialog with the original. Speaker names are matched
+/// ignoring case, so an editor that writes "MAYA:" still matches "Maya".
+pub fn check(
+    original: &[DialogExchange],
+    edited: &[DialogExchange],
+    options: &FidelityOptions,
+) -> FidelityReport {
+    let edited = match_speaker_spelling(original, edited);
+    let edited = edited.as_slice();
+
+    let original_speakers: BTreeSet<&str> = original.iter().map(|e| e.speaker.as_str()).collect();
+    let edited_speakers: BTreeSet<&str> = edited.iter().map(|e| e.speaker.as_str()).collect();
+
+    let changes = align(original, edited, options.min_line_similarity);
+    let kept = changes
+        .iter()
+        .filter(|c| matches!(c, LineChange::Unchanged { .. } | LineChange::Revised { .. }))
+        .count();
+    let score = if original.is_empty() {
+        1.0
+    } else {
+        kept as f64 / original.len() as f64
+    };
+
+    FidelityReport {
+        original: original.to_vec(),
+        edited: edited.to_vec(),
+        invented_speakers: edited_speakers
+            .difference(&original_speakers)
+            .map(|s| s.to_string())
+            .collect(),
+        missing_speakers: original_speakers
+            .difference(&edited_speakers)
+            .map(|s| s.to_string())
+            .collect(),
+        changes,
+        score,
+        options: options.clone(),
+    }
+}
+
+/// The edited lines with each speaker spelled as in the original, when the
+/// names differ only in case
+fn match_speaker_spelling(
+    original: &[DialogExchange],
+    edited: &[DialogExchange],
+) -> Vec<DialogExchange> {
+    edited
+        .iter()
+        .map(|exchange| {
+            let speaker = original
+                .iter()
+                .map(|e| &e.speaker)
+                .find(|name| name.eq_ignore_ascii_case(&exchange.speaker))
+                .unwrap_or(&exchange.speaker);
+            DialogExchange {
+                speaker: speaker.clone(),
+                ..exchange.clone()
+            }
+        })
+        .collect()
+}
+
+/// Dice similarity of two lines' word multisets
+pub fn line_similarity(a: &str, b: &str) -> f64 {
+    let a = analysis::tokens(a);
+    let b = analysis::tokens(b);
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for word in &a {
+        *counts.entry(word).or_default() += 1;
+    }
+    let mut common = 0;
+    for word in &b {
+        if let Some(count) = counts.get_mut(word.as_str())
+            && *count > 0
+        {
+            *count -= 1;
+            common += 1;
+        }
+    }
+
+    2.0 * common as f64 / (a.len() + b.len()) as f64
+}
+
+/// Order-preserving alignment that maximizes total similarity. Lines only
+/// pair up when they share a speaker and are similar enough.
+fn align(original: &[DialogExchange], edited: &[DialogExchange], min: f64) -> Vec<LineChange> {
+    let (n, m) = (original.len(), edited.len());
+    let similarity: Vec<Vec<f64>> = original
+        .iter()
+        .map(|o| {
+            edited
+                .iter()
+                .map(|e| {
+                    if o.speaker == e.speaker {
+                        line_similarity(&o.content, &e.content)
+                    } else {
+                        0.0
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    // best[i][j]: best total for original[i..] and edited[j..]
+    let mut best = vec![vec![0.0_f64; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            let mut value = best[i + 1][j].max(best[i][j + 1]);
+            if similarity[i][j] >= min {
+                value = value.max(similarity[i][j] + best[i + 1][j + 1]);
+            }
+            best[i][j] = value;
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m {
+            let sim = similarity[i][j];
+            if sim >= min && best[i][j] == sim + best[i + 1][j + 1] {
+                changes.push(if original[i].content.trim() == edited[j].content.trim() {
+                    LineChange::Unchanged {
+                        original: i,
+                        edited: j,
+                    }
+                } else {
+                    LineChange::Revised {
+                        original: i,
+                        edited: j,
+                        similarity: sim,
+                    }
+                });
+                i += 1;
+                j += 1;
+                continue;
+            }
+            if best[i][j] == best[i + 1][j] {
+                changes.push(LineChange::Dropped { original: i });
+                i += 1;
+            } else {
+                changes.push(LineChange::Added { edited: j });
+                j += 1;
+            }
+        } else if i < n {
+            changes.push(LineChange::Dropped { original: i });
+            i += 1;
+        } else {
+            changes.push(LineChange::Added { edited: j });
+            j += 1;
+        }
+    }
+
+    changes
+}
+
+impl FidelityReport {
+    /// Relative change in line count
+    pub fn turn_change(&self) -> f64 {
+        if self.original.is_empty() {
+            return 0.0;
+        }
+        (self.edited.len() as f64 - self.original.len() as f64) / self.original.len() as f64
+    }
+
+    /// Reasons the edit falls short; empty when it passes
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if !self.invented_speakers.is_empty() {
+            problems.push(format!(
+                "invented speakers: {}",
+                self.invented_speakers.join(", ")
+            ));
+        }
+        if !self.missing_speakers.is_empty() {
+            problems.push(format!(
+                "missing speakers: {}",
+                self.missing_speakers.join(", ")
+            ));
+        }
+        if self.turn_change().abs() > self.options.turn_tolerance {
+            problems.push(format!(
+                "line count changed from {} to {} ({:+.0}%, tolerance {:.0}%)",
+                self.original.len(),
+                self.edited.len(),
+                self.turn_change() * 100.0,
+                self.options.turn_tolerance * 100.0
+            ));
+        }
+        if self.score < self.options.threshold {
+            problems.push(format!(
+                "only {:.0}% of original lines map to an edited line (threshold {:.0}%)",
+                self.score * 100.0,
+                self.options.threshold * 100.0
+            ));
+        }
+
+        problems
+    }
+
+    pub fn passed(&self) -> bool {
+        self.problems().is_empty()
+    }
+
+    /// Apply `options.on_fail` to an edit outside the limits: a warning to
+    /// show, or an error. `Ok(None)` when the edit passes.
+    pub fn enforce(&self, diff_path: &Path) -> Result<Option<String>> {
+        let problems = self.problems();
+        if problems.is_empty() {
+            return Ok(None);
+        }
+
+        let error = DialogGenError::FidelityFailed(format!(
+            "{}; see {}",
+            problems.join("; "),
+            diff_path.display()
+        ));
+        match self.options.on_fail {
+            FidelityAction::Warn => Ok(Some(error.to_string())),
+            FidelityAction::Fail => Err(error),
+        }
+    }
+
+    /// The `edited-diff` report: a summary followed by each line before and
+    /// after. `=` unchanged, `~` revised, `-` dropped, `+` added.
+    pub fn format(&self) -> String {
+        let count = |f: fn(&LineChange) -> bool| self.changes.iter().filter(|c| f(c)).count();
+        let mut out = String::new();
+
+        let _ = writeln!(
+            out,
+            "Fidelity: {:.0}% of original lines kept ({})",
+            self.score * 100.0,
+            if self.passed() { "pass" } else { "FAIL" }
+        );
+        let _ = writeln!(
+            out,
+            "Lines: {} -> {} ({} unchanged, {} revised, {} dropped, {} added)",
+            self.original.len(),
+            self.edited.len(),
+            count(|c| matches!(c, LineChange::Unchanged { .. })),
+            count(|c| matches!(c, LineChange::Revised { .. })),
+            count(|c| matches!(c, LineChange::Dropped { .. })),
+            count(|c| matches!(c, LineChange::Added { .. })),
+        );
+        for problem in self.problems() {
+            let _ = writeln!(out, "Problem: {}", problem);
+        }
+        out.push('\n');
+
+        let line = |e: &DialogExchange| format!("{}: {}", e.speaker, e.content);
+        for change in &self.changes {
+            match change {
+                LineChange::Unchanged { original, .. } => {
+                    let _ = writeln!(out, "= {}", line(&self.original[*original]));
+                }
+                LineChange::Revised {
+                    original,
+                    edited,
+                    similarity,
+                } => {
+                    let _ = writeln!(out, "~ {}", line(&self.original[*original]));
+                    let _ = writeln!(
+                        out,
+                        "  -> {}  (similarity {:.2})",
+                        line(&self.edited[*edited]),
+                        similarity
+                    );
+                }
+                LineChange::Dropped { original } => {
+                    let _ = writeln!(out, "- {}", line(&self.original[*original]));
+                }
+                LineChange::Added { edited } => {
+                    let _ = writeln!(out, "+ {}", line(&self.edited[*edited]));
+                }
+            }
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange(speaker: &str, content: &str) -> DialogExchange {
+        DialogExchange {
+            speaker: speaker.to_string(),
+            content: content.to_string(),
+            stats: None,
+        }
+    }
+
+    fn original() -> Vec<DialogExchange> {
+        vec![
+            exchange("Maya", "So did you finish the book last night?"),
+            exchange("Dev", "I did and the ending wrecked me."),
+            exchange("Maya", "Right? Nobody saw that twist coming."),
+            exchange("Dev", "Honestly I saw it coming a mile away."),
+        ]
+    }
+
+    #[test]
+    fn identical_edit_passes() {
+        let report = check(&original(), &original(), &FidelityOptions::default());
+
+        assert_eq!(report.score, 1.0);
+        assert!(report.passed());
+        assert!(
+            report
+                .changes
+                .iter()
+                .all(|c| matches!(c, LineChange::Unchanged { .. }))
+        );
+    }
+
+    #[test]
+    fn speaker_case_is_ignored() {
+        let edited: Vec<_> = original()
+            .into_iter()
+            .map(|e| DialogExchange {
+                speaker: e.speaker.to_uppercase(),
+                ..e
+            })
+            .collect();
+        let report = check(&original(), &edited, &FidelityOptions::default());
+
+        assert!(report.invented_speakers.is_empty());
+        assert!(report.passed());
+        assert_eq!(report.edited[0].speaker, "Maya");
+    }
+
+    #[test]
+    fn aligns_revised_dropped_and_added_lines() {
+        let edited = vec![
+            exchange("Maya", "So did you finish the book?"),
+            exchange("Dev", "I did and the ending wrecked me."),
+            exchange("Narrator", "Meanwhile, across town."),
+            exchange("Dev", "Honestly I saw it coming a mile away."),
+        ];
+        let report = check(&original(), &edited, &FidelityOptions::default());
+
+        assert!(matches!(
+            report.changes[0],
+            LineChange::Revised {
+                original: 0,
+                edited: 0,
+                ..
+            }
+        ));
+        assert_eq!(
+            report.changes[1],
+            LineChange::Unchanged {
+                original: 1,
+                edited: 1
+            }
+        );
+        assert!(
+            report
+                .changes
+                .contains(&LineChange::Dropped { original: 2 })
+        );
+        assert!(report.changes.contains(&LineChange::Added { edited: 2 }));
+        assert_eq!(report.score, 0.75);
+        assert_eq!(report.invented_speakers, ["Narrator"]);
+
+        let problems = report.problems();
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("invented speakers: Narrator"));
+        assert!(problems[1].starts_with("only 75% of original lines"));
+    }
+
+    #[test]
+    fn flags_line_count_and_missing_speakers() {
+        let edited = vec![exchange("Maya", "So did you finish the book last night?")];
+        let report = check(&original(), &edited, &FidelityOptions::default());
+
+        assert_eq!(report.missing_speakers, ["Dev"]);
+        assert_eq!(report.turn_change(), -0.75);
+        assert_eq!(report.problems().len(), 3);
+    }
+
+    #[test]
+    fn enforce_warns_or_fails() {
+        let edited = vec![exchange("Maya", "Completely different.")];
+        let diff = Path::new("edited-diff1.txt");
+
+        let report = check(&original(), &edited, &FidelityOptions::default());
+        let warning = report.enforce(diff).unwrap().unwrap();
+        assert!(warning.ends_with("see edited-diff1.txt"));
+
+        let options = FidelityOptions {
+            on_fail: FidelityAction::Fail,
+            ..Default::default()
+        };
+        let report = check(&original(), &edited, &options);
+        assert!(matches!(
+            report.enforce(diff),
+            Err(DialogGenError::FidelityFailed(_))
+        ));
+
+        let report = check(&original(), &original(), &options);
+        assert!(report.enforce(diff).unwrap().is_none());
+    }
+
+    #[test]
+    fn similarity_counts_shared_words() {
+        assert_eq!(line_similarity("", ""), 1.0);
+        assert_eq!(line_similarity("a b", "a b"), 1.0);
+        assert_eq!(line_similarity("a b", "c d"), 0.0);
+        assert_eq!(line_similarity("a b c d", "a b"), 2.0 * 2.0 / 6.0);
+    }
+}
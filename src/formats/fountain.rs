@@ -0,0 +1,194 @@
+//! Fountain screenplay export and import
+//!
+//! Export writes a forced scene heading from the scene name, the setting as
+//! an action paragraph, then character cues with dialog and parentheticals.
+//! Import reads the dialog blocks of a Fountain script back as seed lines.
+
+use crate::config::{DialogLine, Directions, Speaker};
+use crate::error::{DialogGenError, Result};
+use crate::formats::{Segment, split_cues};
+use crate::orchestrator::GeneratedDialog;
+
+/// Render the dialog as a Fountain screenplay
+pub fn render(dialog: &GeneratedDialog, directions: &Directions) -> String {
+    let mut out = String::new();
+
+    if !directions.scene_name.is_empty() {
+        out.push_str(&format!("Title: {}\n\n", directions.scene_name));
+        out.push_str(&format!(".{}\n\n", directions.scene_name.to_uppercase()));
+    }
+    if !directions.setting.is_empty() {
+        out.push_str(&format!("{}\n\n", directions.setting));
+    }
+
+    for exchange in &dialog.exchanges {
+        out.push_str(&exchange.speaker.to_uppercase());
+        out.push('\n');
+        for segment in split_cues(&exchange.content) {
+            match segment {
+                Segment::Text(text) => out.push_str(&text),
+                Segment::Cue(cue) => out.push_str(&format!("({})", cue)),
+            }
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Parse the dialog blocks of a Fountain script into seed lines.
+/// Character cues are matched to the speakers' names case-insensitively
+/// ("MAYA" is Maya); a cue that matches no speaker is an error.
+pub fn parse(content: &str, speakers: &[Speaker]) -> Result<Vec<DialogLine>> {
+    let content = content.replace("\r\n", "\n");
+    let lines: Vec<&str> = strip_title_page(&content).lines().collect();
+    let mut result = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i].trim();
+        let after_blank = i == 0 || lines[i - 1].trim().is_empty();
+        let has_dialog = lines.get(i + 1).is_some_and(|next| !next.trim().is_empty());
+
+        let Some(name) = cue_name(line).filter(|_| after_blank && has_dialog) else {
+            i += 1;
+            continue;
+        };
+
+        // Dialog runs until the next blank line
+        let mut parts = Vec::new();
+        i += 1;
+        while i < lines.len() && !lines[i].trim().is_empty() {
+            parts.push(lines[i].trim());
+            i += 1;
+        }
+
+        let content = parts.join(" ");
+        if !content.is_empty() {
+            result.push(DialogLine {
+                speaker: resolve_speaker(&name, speakers)?,
+                content,
+            });
+        }
+    }
+
+    Ok(result)
+}
+
+/// Return the character name if the line is a character cue
+fn cue_name(line: &str) -> Option<String> {
+    if let Some(forced) = line.strip_prefix('@') {
+        return Some(strip_extension(forced));
+    }
+
+    // Scene headings, transitions, and other forced elements
+    let upper = line.to_uppercase();
+    if line.is_empty()
+        || line.starts_with(['.', '!', '>', '#', '=', '~', '['])
+        || ["INT", "EXT", "EST", "I/E"]
+            .iter()
+            .any(|p| upper.starts_with(&format!("{}.", p)) || upper.starts_with(&format!("{} ", p)))
+        || line.ends_with("TO:")
+    {
+        return None;
+    }
+
+    let name = strip_extension(line);
+    let is_cue = name.chars().any(|c| c.is_alphabetic())
+        && !name.chars().any(|c| c.is_lowercase())
+        && !name.ends_with(':');
+    is_cue.then_some(name)
+}
+
+/// Drop extensions like "(V.O.)" or "(CONT'D)" and the dual dialog caret
+fn strip_extension(cue: &str) -> String {
+    let cue = cue.trim().trim_end_matches('^').trim();
+    match cue.find('(') {
+        Some(pos) => cue[..pos].trim().to_string(),
+        None => cue.to_string(),
+    }
+}
+
+/// The title page is the leading block of "Key: Value" lines
+fn strip_title_page(content: &str) -> &str {
+    let first_line = content.lines().next().unwrap_or_default();
+    let is_title_key = first_line.find(':').is_some_and(|pos| {
+        pos > 0
+            && first_line[..pos]
+                .chars()
+                .all(|c| c.is_alphabetic() || c.is_whitespace())
+    });
+
+    if !is_title_key {
+        return content;
+    }
+
+    match content.find("\n\n") {
+        Some(pos) => &content[pos + 2..],
+        None => "",
+    }
+}
+
+fn resolve_speaker(cue: &str, speakers: &[Speaker]) -> Result<String> {
+    match speakers.iter().find(|s| s.name.eq_ignore_ascii_case(cue)) {
+        Some(speaker) => Ok(speaker.name.clone()),
+        None => {
+            let names: Vec<&str> = speakers.iter().map(|s| s.name.as_str()).collect();
+            Err(DialogGenError::InvalidConfig(format!(
+                "Fountain character cue '{}' matches no speaker ({})",
+                cue,
+                names.join(", ")
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(parsed: &[DialogLine]) -> Vec<String> {
+        parsed
+            .iter()
+            .map(|l| format!("{}: {}", l.speaker, l.content))
+            .collect()
+    }
+
+    #[test]
+    fn parses_dialog_after_title_page() {
+        let script = "Title: Book Club\nAuthor: Someone\n\nINT. CAFE - DAY\n\nMaya waves.\n\nMAYA\nYou finished it?\n\nDEV (V.O.)\n(quietly)\nLast night.\n\nCUT TO:\n\n@Maya\nToo late.\n";
+        let speakers = [Speaker::new("Maya"), Speaker::new("Dev")];
+
+        assert_eq!(
+            lines(&parse(script, &speakers).unwrap()),
+            [
+                "Maya: You finished it?",
+                "Dev: (quietly) Last night.",
+                "Maya: Too late."
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_crlf_scripts() {
+        let script = "Title: Book Club\r\n\r\nMAYA\r\nHello.\r\n\r\nDEV ^\r\nHi.\r\n";
+        assert_eq!(
+            lines(&parse(script, &[Speaker::new("Maya"), Speaker::new("Dev")]).unwrap()),
+            ["Maya: Hello.", "Dev: Hi."]
+        );
+    }
+
+    #[test]
+    fn unknown_character_cues_are_rejected() {
+        let script = "MAYA\nHello.\n\nNARRATOR (V.O.)\nMeanwhile.\n";
+        let error = parse(script, &[Speaker::new("Maya"), Speaker::new("Dev")]).unwrap_err();
+
+        assert!(matches!(error, DialogGenError::InvalidConfig(_)));
+        assert!(
+            error
+                .to_string()
+                .contains("cue 'NARRATOR' matches no speaker (Maya, Dev)")
+        );
+    }
+}
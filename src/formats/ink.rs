@@ -0,0 +1,48 @@
+//! Ink story export
+//!
+//! The dialog becomes a single knot named after the scene, one line per
+//! exchange with `speaker` and `id` tags for game engines and localization.
+
+use crate::config::Directions;
+use crate::formats::identifier;
+use crate::orchestrator::GeneratedDialog;
+
+/// Render the dialog as an Ink story
+pub fn render(dialog: &GeneratedDialog, directions: &Directions) -> String {
+    let knot = identifier(&directions.scene_name);
+    let mut out = String::new();
+
+    if !directions.scene_name.is_empty() {
+        out.push_str(&format!("// {}\n", directions.scene_name));
+    }
+    out.push_str(&format!("-> {}\n\n=== {} ===\n", knot, knot));
+
+    for (i, exchange) in dialog.exchanges.iter().enumerate() {
+        out.push_str(&format!(
+            "{}: {} #speaker:{} #id:{}_{:03}\n",
+            escape(&exchange.speaker),
+            escape(&exchange.content),
+            identifier(&exchange.speaker),
+            knot,
+            i + 1
+        ));
+    }
+
+    out.push_str("-> END\n");
+    out
+}
+
+/// Backslash-escape characters Ink would treat as markup
+fn escape(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '#' | '{' | '}' | '[' | ']' | '|' | '~' | '<' | '>' | '/'
+        ) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
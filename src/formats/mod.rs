@@ -0,0 +1,310 @@
+//! Exporters that turn a generated dialog into formats other tools consume
+
+pub mod fountain;
+pub mod ink;
+pub mod ssml;
+pub mod subtitles;
+pub mod yarn;
+
+use crate::error::{DialogGenError, Result};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Output formats that can be written alongside the plain-text dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ExportFormat {
+    /// One combined SSML document for the whole dialog
+    Ssml,
+    /// One SSML document per dialog line
+    SsmlLines,
+    /// SubRip captions with estimated timings
+    Srt,
+    /// WebVTT captions with estimated timings
+    Vtt,
+    /// Fountain screenplay
+    Fountain,
+    /// Ink story for game engines
+    Ink,
+    /// Yarn Spinner node for game engines
+    Yarn,
+}
+
+impl ExportFormat {
+    /// Every export format, in the order `--help` lists them
+    pub const ALL: &[ExportFormat] = &[
+        ExportFormat::Ssml,
+        ExportFormat::SsmlLines,
+        ExportFormat::Srt,
+        ExportFormat::Vtt,
+        ExportFormat::Fountain,
+        ExportFormat::Ink,
+        ExportFormat::Yarn,
+    ];
+
+    /// Name used on the command line and in scene files
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportFormat::Ssml => "ssml",
+            ExportFormat::SsmlLines => "ssml-lines",
+            ExportFormat::Srt => "srt",
+            ExportFormat::Vtt => "vtt",
+            ExportFormat::Fountain => "fountain",
+            ExportFormat::Ink => "ink",
+            ExportFormat::Yarn => "yarn",
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = DialogGenError;
+
+    /// Parse a name, ignoring case
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|v| v.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| DialogGenError::InvalidConfig(format!("unknown export format '{}'", s)))
+    }
+}
+
+/// Settings shared by the exporters
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// Silence between turns in subtitle timings
+    pub subtitle_gap: Duration,
+    /// Measured line durations to use instead of estimates
+    pub tts_manifest: Option<PathBuf>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            subtitle_gap: Duration::from_millis(300),
+            tts_manifest: None,
+        }
+    }
+}
+
+/// A piece of dialog content: spoken text or a stage/emotion cue
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Text(String),
+    Cue(String),
+}
+
+/// What a stage cue does to the delivery of a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CueKind {
+    LongPause,
+    Pause,
+    /// A non-verbal sound such as a laugh or sigh
+    Sound,
+    Emotion(Emotion),
+    /// A silent gesture such as a nod or shrug
+    Action,
+}
+
+/// Emotions a cue can ask for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emotion {
+    Excited,
+    Whisper,
+    Shout,
+    Sad,
+    Nervous,
+    Sarcastic,
+    Angry,
+}
+
+/// Longest bracketed span still read as a cue, in words
+const MAX_CUE_WORDS: usize = 3;
+
+const PAUSE_WORDS: &[&str] = &["pause", "beat", "silence", "hesitates"];
+const SOUND_WORDS: &[&str] = &["laugh", "chuckle", "sigh", "giggle", "scoff"];
+const ACTION_WORDS: &[&str] = &[
+    "lean", "nod", "shrug", "smil", "grin", "wave", "wink", "frown", "glanc", "stare", "look",
+    "point", "gestur", "sip", "roll",
+];
+const EMOTION_WORDS: &[(&[&str], Emotion)] = &[
+    (
+        &["excited", "enthusiastic", "eager", "fast"],
+        Emotion::Excited,
+    ),
+    (&["whisper", "quiet", "softly"], Emotion::Whisper),
+    (&["shout", "yell", "loud"], Emotion::Shout),
+    (&["sad", "somber", "slowly", "tired"], Emotion::Sad),
+    (&["nervous", "anxious", "rushed"], Emotion::Nervous),
+    (&["sarcastic", "dry", "deadpan"], Emotion::Sarcastic),
+    (&["angry", "frustrated"], Emotion::Angry),
+];
+
+/// Classify the text of a bracketed span. Only short spans built around a
+/// known pause, sound, emotion or gesture word are cues ("laughs", "long
+/// pause", "excitedly", "leans forward"); anything else, like "(which I
+/// loved)", is spoken.
+pub fn cue_kind(cue: &str) -> Option<CueKind> {
+    let cue = cue.to_lowercase();
+    let words: Vec<&str> = cue
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .collect();
+    if words.is_empty() || words.len() > MAX_CUE_WORDS {
+        return None;
+    }
+    let has = |vocabulary: &[&str]| {
+        words
+            .iter()
+            .any(|word| vocabulary.iter().any(|v| word.starts_with(v)))
+    };
+
+    if has(PAUSE_WORDS) {
+        Some(if words.contains(&"long") {
+            CueKind::LongPause
+        } else {
+            CueKind::Pause
+        })
+    } else if has(SOUND_WORDS) {
+        Some(CueKind::Sound)
+    } else if let Some((_, emotion)) = EMOTION_WORDS.iter().find(|(vocabulary, _)| has(vocabulary))
+    {
+        Some(CueKind::Emotion(*emotion))
+    } else {
+        has(ACTION_WORDS).then_some(CueKind::Action)
+    }
+}
+
+/// Split dialog content into spoken text and cues written as
+/// "(laughs)" or "[pause]". Bracketed spans that aren't cues (see
+/// [`cue_kind`]) stay in the text, brackets included.
+pub fn split_cues(content: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let close = match c {
+            '(' => ')',
+            '[' => ']',
+            _ => {
+                text.push(c);
+                continue;
+            }
+        };
+
+        let mut cue = String::new();
+        let mut closed = false;
+        for inner in chars.by_ref() {
+            if inner == close {
+                closed = true;
+                break;
+            }
+            cue.push(inner);
+        }
+
+        if !closed {
+            // Unbalanced bracket - keep it as spoken text
+            text.push(c);
+            text.push_str(&cue);
+            continue;
+        }
+        if cue_kind(&cue).is_none() {
+            // A spoken aside, not a stage cue
+            text.push(c);
+            text.push_str(&cue);
+            text.push(close);
+            continue;
+        }
+
+        push_text(&mut segments, &mut text);
+        segments.push(Segment::Cue(cue.trim().to_lowercase()));
+    }
+
+    push_text(&mut segments, &mut text);
+    segments
+}
+
+fn push_text(segments: &mut Vec<Segment>, text: &mut String) {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !collapsed.is_empty() {
+        segments.push(Segment::Text(collapsed));
+    }
+    text.clear();
+}
+
+/// Turn a display name into a snake_case identifier usable as an Ink knot,
+/// Yarn node title or line id prefix
+pub fn identifier(name: &str) -> String {
+    let mut id = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            id.push(c.to_ascii_lowercase());
+        } else if !id.is_empty() && !id.ends_with('_') {
+            id.push('_');
+        }
+    }
+
+    let id = id.trim_end_matches('_');
+    match id.chars().next() {
+        None => "dialog".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("scene_{}", id),
+        Some(_) => id.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_known_cues_are_split_out() {
+        assert_eq!(
+            split_cues("Okay (laughs) the sequel (which I loved) is [beat] out (unbalanced"),
+            [
+                Segment::Text("Okay".to_string()),
+                Segment::Cue("laughs".to_string()),
+                Segment::Text("the sequel (which I loved) is".to_string()),
+                Segment::Cue("beat".to_string()),
+                Segment::Text("out (unbalanced".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn export_formats_parse_by_name() {
+        for format in ExportFormat::ALL {
+            assert_eq!(
+                format.to_string().parse::<ExportFormat>().ok(),
+                Some(*format)
+            );
+        }
+        assert_eq!(
+            "SSML-Lines".parse::<ExportFormat>().ok(),
+            Some(ExportFormat::SsmlLines)
+        );
+        assert!("pdf".parse::<ExportFormat>().is_err());
+    }
+
+    #[test]
+    fn classifies_cues_by_word() {
+        assert_eq!(cue_kind("Long pause"), Some(CueKind::LongPause));
+        assert_eq!(cue_kind("sighs"), Some(CueKind::Sound));
+        assert_eq!(
+            cue_kind("excitedly"),
+            Some(CueKind::Emotion(Emotion::Excited))
+        );
+        assert_eq!(cue_kind("leans forward"), Some(CueKind::Action));
+        assert_eq!(cue_kind("breakfast"), None);
+        assert_eq!(cue_kind("laughing at the ending, honestly"), None);
+        assert_eq!(cue_kind(""), None);
+    }
+}
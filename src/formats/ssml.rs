@@ -0,0 +1,145 @@
+//! SSML export for TTS engines
+//!
+//! Each exchange is wrapped in a `<voice>` element named by the speaker's
+//! `Voice:` field. Cues like "(laughs)" or "[pause]" become `<break>`
+//! elements, and emotion cues like "(excited)" wrap the text that follows
+//! in a `<prosody>` element. Gestures like "(nods)" are left out; other
+//! parentheticals are spoken.
+
+use crate::config::Speaker;
+use crate::formats::{CueKind, Emotion, Segment, cue_kind, split_cues};
+use crate::orchestrator::{DialogExchange, GeneratedDialog};
+
+const SPEAK_OPEN: &str =
+    r#"<speak version="1.1" xmlns="http://www.w3.org/2001/10/synthesis" xml:lang="en-US">"#;
+
+/// Render the whole dialog as a single SSML document
+pub fn render_combined(dialog: &GeneratedDialog, speakers: &[Speaker]) -> String {
+    let mut doc = format!("<?xml version=\"1.0\"?>\n{}\n", SPEAK_OPEN);
+    for exchange in &dialog.exchanges {
+        doc.push_str(&render_exchange(exchange, speakers));
+    }
+    doc.push_str("</speak>\n");
+    doc
+}
+
+/// Render one SSML document per exchange, in dialog order
+pub fn render_lines(dialog: &GeneratedDialog, speakers: &[Speaker]) -> Vec<String> {
+    dialog
+        .exchanges
+        .iter()
+        .map(|exchange| {
+            format!(
+                "<?xml version=\"1.0\"?>\n{}\n{}</speak>\n",
+                SPEAK_OPEN,
+                render_exchange(exchange, speakers)
+            )
+        })
+        .collect()
+}
+
+fn render_exchange(exchange: &DialogExchange, speakers: &[Speaker]) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut prosody_open = false;
+
+    for segment in split_cues(&exchange.content) {
+        match segment {
+            Segment::Text(text) => parts.push(escape(&text)),
+            Segment::Cue(cue) => match cue_kind(&cue) {
+                Some(CueKind::Emotion(emotion)) => {
+                    if prosody_open {
+                        parts.push("</prosody>".to_string());
+                    }
+                    parts.push(format!("<prosody {}>", prosody(emotion)));
+                    prosody_open = true;
+                }
+                // Gestures have no spoken equivalent
+                Some(CueKind::Action) | None => {}
+                Some(kind) => parts.push(cue_break(kind).to_string()),
+            },
+        }
+    }
+
+    if prosody_open {
+        parts.push("</prosody>".to_string());
+    }
+    let body = parts.join(" ");
+
+    let voice = speakers
+        .iter()
+        .find(|s| s.name == exchange.speaker)
+        .and_then(|s| s.voice.as_deref());
+
+    match voice {
+        Some(name) => format!(
+            "  <voice name=\"{}\"><p>{}</p></voice>\n",
+            escape(name),
+            body
+        ),
+        None => format!("  <p>{}</p>\n", body),
+    }
+}
+
+/// Map pause and sound cues to breaks
+fn cue_break(kind: CueKind) -> &'static str {
+    match kind {
+        CueKind::LongPause => "<break time=\"1s\"/>",
+        CueKind::Pause => "<break time=\"500ms\"/>",
+        _ => "<break strength=\"medium\"/>",
+    }
+}
+
+/// Map emotion cues to prosody attributes
+fn prosody(emotion: Emotion) -> &'static str {
+    match emotion {
+        Emotion::Excited => "rate=\"fast\" pitch=\"+10%\"",
+        Emotion::Whisper => "volume=\"x-soft\"",
+        Emotion::Shout => "volume=\"x-loud\"",
+        Emotion::Sad => "rate=\"slow\" pitch=\"-10%\"",
+        Emotion::Nervous => "rate=\"fast\"",
+        Emotion::Sarcastic => "pitch=\"-5%\" rate=\"slow\"",
+        Emotion::Angry => "volume=\"loud\" pitch=\"-5%\"",
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange(content: &str) -> DialogExchange {
+        DialogExchange {
+            speaker: "Maya".to_string(),
+            content: content.to_string(),
+            stats: None,
+        }
+    }
+
+    #[test]
+    fn spoken_parentheticals_are_kept() {
+        assert_eq!(
+            render_exchange(&exchange("The sequel (which I loved) is out & <b>"), &[]),
+            "  <p>The sequel (which I loved) is out &amp; &lt;b&gt;</p>\n"
+        );
+    }
+
+    #[test]
+    fn cues_become_breaks_and_prosody() {
+        let speakers = [Speaker::new("Maya").with_voice("en-US-Jenny")];
+        assert_eq!(
+            render_exchange(
+                &exchange("(laughs) No way. [long pause] (whispering) Tell me."),
+                &speakers
+            ),
+            "  <voice name=\"en-US-Jenny\"><p><break strength=\"medium\"/> No way. \
+             <break time=\"1s\"/> <prosody volume=\"x-soft\"> Tell me. </prosody></p></voice>\n"
+        );
+    }
+}
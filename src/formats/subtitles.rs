@@ -0,0 +1,265 @@
+//! SRT and WebVTT captions with estimated timings
+//!
+//! Each exchange's duration is estimated from its spoken word count and the
+//! speaker's `Speaking Rate:` (words per minute). Estimates can be replaced
+//! with real per-line durations from a TTS manifest.
+
+use crate::config::Speaker;
+use crate::error::Result;
+use crate::formats::{Segment, split_cues};
+use crate::orchestrator::GeneratedDialog;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// Speaking rate used when a speaker file doesn't set one
+pub const DEFAULT_WORDS_PER_MINUTE: f32 = 150.0;
+
+/// Shortest time a caption stays on screen
+const MIN_CUE_DURATION: Duration = Duration::from_millis(1000);
+
+/// A single caption
+#[derive(Debug, Clone)]
+pub struct Cue {
+    pub start: Duration,
+    pub end: Duration,
+    pub speaker: String,
+    pub text: String,
+}
+
+/// Measured duration of one line, as written by a TTS pipeline
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestEntry {
+    /// 1-based line number in the dialog
+    pub line: usize,
+    pub duration_ms: u64,
+}
+
+/// Load a TTS manifest: a JSON array of `{"line": N, "duration_ms": M}`
+pub fn load_tts_manifest(path: &Path) -> Result<Vec<ManifestEntry>> {
+    let content = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Estimate how long each exchange takes to speak
+pub fn estimate_durations(dialog: &GeneratedDialog, speakers: &[Speaker]) -> Vec<Duration> {
+    dialog
+        .exchanges
+        .iter()
+        .map(|exchange| {
+            let wpm = speakers
+                .iter()
+                .find(|s| s.name == exchange.speaker)
+                .and_then(|s| s.speaking_rate)
+                .filter(|&rate| rate > 0.0)
+                .unwrap_or(DEFAULT_WORDS_PER_MINUTE);
+
+            // Spoken asides like "(which I loved)" count; stage cues don't
+            let words = spoken_text(&exchange.content).split_whitespace().count();
+            let millis = words as f64 * 60_000.0 / f64::from(wpm);
+            let estimate = Duration::from_millis(millis.round() as u64);
+            estimate.max(MIN_CUE_DURATION)
+        })
+        .collect()
+}
+
+/// Replace estimated durations with measured ones where the manifest has them
+pub fn apply_manifest(durations: &mut [Duration], manifest: &[ManifestEntry]) {
+    for entry in manifest {
+        if let Some(slot) = entry.line.checked_sub(1).and_then(|i| durations.get_mut(i)) {
+            *slot = Duration::from_millis(entry.duration_ms);
+        }
+    }
+}
+
+/// Lay the exchanges out back to back with `gap` between turns
+pub fn build_cues(dialog: &GeneratedDialog, durations: &[Duration], gap: Duration) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    let mut start = Duration::ZERO;
+
+    for (exchange, duration) in dialog.exchanges.iter().zip(durations) {
+        let end = start + *duration;
+        cues.push(Cue {
+            start,
+            end,
+            speaker: exchange.speaker.clone(),
+            text: spoken_text(&exchange.content),
+        });
+        start = end + gap;
+    }
+
+    cues
+}
+
+/// Render cues as SubRip (.srt)
+pub fn render_srt(cues: &[Cue]) -> String {
+    let mut out = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        out.push_str(&format!(
+            "{}\n{} --> {}\n{}: {}\n\n",
+            i + 1,
+            timestamp(cue.start, ','),
+            timestamp(cue.end, ','),
+            cue.speaker,
+            cue.text
+        ));
+    }
+    out
+}
+
+/// Render cues as WebVTT (.vtt) with voice spans for speaker labels
+pub fn render_vtt(cues: &[Cue]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for (i, cue) in cues.iter().enumerate() {
+        out.push_str(&format!(
+            "{}\n{} --> {}\n<v {}>{}\n\n",
+            i + 1,
+            timestamp(cue.start, '.'),
+            timestamp(cue.end, '.'),
+            escape(&cue.speaker),
+            escape(&cue.text)
+        ));
+    }
+    out
+}
+
+/// Escape the characters WebVTT cue text treats as markup
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Dialog content with stage cues removed
+fn spoken_text(content: &str) -> String {
+    split_cues(content)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Text(text) => Some(text),
+            Segment::Cue(_) => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Format as HH:MM:SS,mmm (SRT) or HH:MM:SS.mmm (WebVTT)
+fn timestamp(time: Duration, separator: char) -> String {
+    let millis = time.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        (millis / 60_000) % 60,
+        (millis / 1000) % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orchestrator::DialogExchange;
+
+    fn dialog(lines: &[(&str, &str)]) -> GeneratedDialog {
+        let exchanges = lines
+            .iter()
+            .map(|(speaker, content)| DialogExchange {
+                speaker: speaker.to_string(),
+                content: content.to_string(),
+                stats: None,
+            })
+            .collect();
+        GeneratedDialog::from_script(exchanges, "test", 0.7, &[])
+    }
+
+    #[test]
+    fn estimates_from_spoken_words_and_rate() {
+        let dialog = dialog(&[
+            (
+                "Maya",
+                "one two three four five (laughs) six seven eight nine ten",
+            ),
+            ("Dev", "Hi."),
+        ]);
+        let speakers = [Speaker::new("Maya").with_speaking_rate(120.0)];
+
+        assert_eq!(
+            estimate_durations(&dialog, &speakers),
+            [Duration::from_secs(5), MIN_CUE_DURATION]
+        );
+    }
+
+    #[test]
+    fn spoken_asides_are_captioned_and_timed() {
+        let dialog = dialog(&[("Maya", "The sequel (which I loved) is out (sighs)")]);
+        let durations = estimate_durations(&dialog, &[]);
+        let cues = build_cues(&dialog, &durations, Duration::ZERO);
+
+        assert_eq!(durations, [Duration::from_millis(2800)]);
+        assert_eq!(cues[0].text, "The sequel (which I loved) is out");
+    }
+
+    #[test]
+    fn manifest_overrides_estimates() {
+        let mut durations = vec![Duration::from_secs(1); 2];
+        let manifest = [
+            ManifestEntry {
+                line: 2,
+                duration_ms: 2500,
+            },
+            ManifestEntry {
+                line: 0,
+                duration_ms: 9,
+            },
+            ManifestEntry {
+                line: 7,
+                duration_ms: 9,
+            },
+        ];
+        apply_manifest(&mut durations, &manifest);
+
+        assert_eq!(
+            durations,
+            [Duration::from_secs(1), Duration::from_millis(2500)]
+        );
+    }
+
+    #[test]
+    fn cues_are_laid_out_with_gaps() {
+        let dialog = dialog(&[("Maya", "Hello (sighs) there."), ("Dev", "Hi.")]);
+        let durations = [Duration::from_millis(1500), Duration::from_millis(61_000)];
+        let cues = build_cues(&dialog, &durations, Duration::from_millis(300));
+
+        assert_eq!(cues[0].text, "Hello there.");
+        assert_eq!(cues[1].start, Duration::from_millis(1800));
+        assert_eq!(cues[1].end, Duration::from_millis(62_800));
+
+        assert_eq!(
+            render_srt(&cues),
+            "1\n00:00:00,000 --> 00:00:01,500\nMaya: Hello there.\n\n\
+             2\n00:00:01,800 --> 00:01:02,800\nDev: Hi.\n\n"
+        );
+        assert!(
+            render_vtt(&cues).starts_with(
+                "WEBVTT\n\n1\n00:00:00.000 --> 00:00:01.500\n<v Maya>Hello there.\n\n"
+            )
+        );
+    }
+
+    #[test]
+    fn vtt_escapes_markup() {
+        let dialog = dialog(&[("Maya & Co", "a < b > c")]);
+        let cues = build_cues(&dialog, &[Duration::from_secs(1)], Duration::ZERO);
+
+        assert!(render_vtt(&cues).contains("<v Maya &amp; Co>a &lt; b &gt; c\n"));
+    }
+
+    #[test]
+    fn timestamps_roll_over_to_hours() {
+        assert_eq!(
+            timestamp(Duration::from_millis(3_723_004), ','),
+            "01:02:03,004"
+        );
+    }
+}
@@ -0,0 +1,44 @@
+//! Yarn Spinner export
+//!
+//! The dialog becomes a single node titled after the scene. Each line keeps
+//! Yarn's "Character: text" form and carries `#speaker` and `#line` tags,
+//! the latter being what Yarn Spinner uses for localization.
+
+use crate::config::Directions;
+use crate::formats::identifier;
+use crate::orchestrator::GeneratedDialog;
+
+/// Render the dialog as a Yarn Spinner node
+pub fn render(dialog: &GeneratedDialog, directions: &Directions) -> String {
+    let title = identifier(&directions.scene_name);
+    let mut out = format!("title: {}\n---\n", title);
+    if !directions.scene_name.is_empty() {
+        out.push_str(&format!("// {}\n", directions.scene_name));
+    }
+
+    for (i, exchange) in dialog.exchanges.iter().enumerate() {
+        out.push_str(&format!(
+            "{}: {} #speaker:{} #line:{}_{:03}\n",
+            escape(&exchange.speaker),
+            escape(&exchange.content),
+            identifier(&exchange.speaker),
+            title,
+            i + 1
+        ));
+    }
+
+    out.push_str("===\n");
+    out
+}
+
+/// Backslash-escape characters Yarn would treat as markup
+fn escape(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        if matches!(c, '\\' | '#' | '{' | '}' | '[' | ']' | '<' | '>' | '/') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,263 @@
+//! LLM-as-judge evaluation of a finished dialog
+//!
+//! The dialog is sent to a judge model together with the speaker profiles
+//! and director's notes. The judge scores a fixed rubric from 1 to 5 and
+//! writes a short critique, returned as structured JSON. Scores given as
+//! fractions ("4.5") or strings ("4") are rounded and clamped to the scale.
+
+use crate::backend::ChatBackend;
+use crate::config::{DialogConfig, Speaker};
+use crate::error::{DialogGenError, Result};
+use crate::ollama::{ChatMessage, ChatOptions, ChatStats, OllamaClient, extract_json};
+use crate::orchestrator::DialogExchange;
+use serde::{Deserialize, Serialize};
+
+const JUDGE_SYSTEM_PROMPT: &str = r#"You are a strict script judge evaluating a generated dialog against its character profiles and director's notes.
+
+Score each criterion from 1 (poor) to 5 (excellent):
+- in_character: each speaker's lines match their background, personality and speaking style
+- goal_achieved: the dialog achieves the director's stated goal
+- mood_match: the tone matches the requested mood
+- naturalness: it sounds like real people talking, not a script or essay
+- engagement: a listener would want to keep listening
+
+Respond with JSON only, in exactly this shape:
+{"in_character": 1-5, "goal_achieved": 1-5, "mood_match": 1-5, "naturalness": 1-5, "engagement": 1-5, "critique": "two or three sentences"}"#;
+
+/// Rubric scores, each from 1 to 5
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JudgeScores {
+    pub in_character: u8,
+    pub goal_achieved: u8,
+    pub mood_match: u8,
+    pub naturalness: u8,
+    pub engagement: u8,
+}
+
+impl JudgeScores {
+    /// Mean of all criteria
+    pub fn overall(&self) -> f64 {
+        let sum = self.in_character as u32
+            + self.goal_achieved as u32
+            + self.mood_match as u32
+            + self.naturalness as u32
+            + self.engagement as u32;
+        sum as f64 / 5.0
+    }
+}
+
+/// The judge's structured verdict
+#[derive(Debug, Clone, Serialize)]
+pub struct JudgeVerdict {
+    pub judge_model: String,
+    pub scores: JudgeScores,
+    pub overall: f64,
+    pub critique: String,
+}
+
+/// A score as the judge wrote it: a number, possibly fractional, or a
+/// number in a string
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawScore {
+    Number(f64),
+    Text(String),
+}
+
+impl RawScore {
+    /// Round to the nearest whole score and clamp it to 1-5
+    fn to_scale(&self, criterion: &str) -> Result<u8> {
+        let value = match self {
+            RawScore::Number(value) => Some(*value),
+            RawScore::Text(text) => text.trim().parse::<f64>().ok(),
+        };
+        match value.filter(|v| v.is_finite()) {
+            Some(value) => Ok(value.round().clamp(1.0, 5.0) as u8),
+            None => Err(DialogGenError::GenerationFailed(format!(
+                "judge gave {} a score that isn't a number",
+                criterion
+            ))),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawVerdict {
+    in_character: RawScore,
+    goal_achieved: RawScore,
+    mood_match: RawScore,
+    naturalness: RawScore,
+    engagement: RawScore,
+    #[serde(default)]
+    critique: String,
+}
+
+/// Parse the judge's reply into scores and a critique
+fn parse_verdict(reply: &str) -> Result<(JudgeScores, String)> {
+    let raw: RawVerdict = serde_json::from_str(extract_json(reply)).map_err(|e| {
+        DialogGenError::GenerationFailed(format!("judge returned invalid JSON ({}): {}", e, reply))
+    })?;
+
+    let scores = JudgeScores {
+        in_character: raw.in_character.to_scale("in_character")?,
+        goal_achieved: raw.goal_achieved.to_scale("goal_achieved")?,
+        mood_match: raw.mood_match.to_scale("mood_match")?,
+        naturalness: raw.naturalness.to_scale("naturalness")?,
+        engagement: raw.engagement.to_scale("engagement")?,
+    };
+    Ok((scores, raw.critique.trim().to_string()))
+}
+
+/// Scores dialogs with a judge model. Use a different model from the one
+/// that generated the dialog, so it isn't grading its own output.
+pub struct DialogJudge<B = OllamaClient> {
+    backend: B,
+}
+
+impl<B: ChatBackend> DialogJudge<B> {
+    pub fn new(backend: B) -> Self {
+        Self { backend }
+    }
+
+    /// Score a dialog against the scene it was generated from
+    pub async fn judge(
+        &self,
+        exchanges: &[DialogExchange],
+        config: &DialogConfig,
+    ) -> Result<(JudgeVerdict, ChatStats)> {
+        let messages = vec![ChatMessage {
+            role: "user".to_string(),
+            content: Self::build_request(exchanges, config),
+        }];
+
+        // Judging should be as repeatable as the model allows
+        let options = ChatOptions {
+            temperature: 0.0,
+            seed: Some(0),
+        };
+
+        let result = self
+            .backend
+            .chat_json(JUDGE_SYSTEM_PROMPT, &messages, &options)
+            .await?;
+
+        let (scores, critique) = parse_verdict(&result.content)?;
+        let verdict = JudgeVerdict {
+            judge_model: self.backend.model().to_string(),
+            overall: scores.overall(),
+            scores,
+            critique,
+        };
+
+        Ok((verdict, result.stats))
+    }
+
+    fn build_request(exchanges: &[DialogExchange], config: &DialogConfig) -> String {
+        let directions = &config.directions;
+        let mut request = String::from("CHARACTERS\n\n");
+        request.push_str(&Self::profile(&config.speaker1));
+        request.push_str(&Self::profile(&config.speaker2));
+
+        request.push_str(&format!(
+            "DIRECTIONS\n\nScene: {}\nSetting: {}\nMood: {}\nGoal: {}\nNotes: {}\n\nDIALOG\n\n",
+            directions.scene_name,
+            directions.setting,
+            directions.mood,
+            directions.goal,
+            directions.notes
+        ));
+
+        for exchange in exchanges {
+            request.push_str(&format!("{}: {}\n", exchange.speaker, exchange.content));
+        }
+
+        request
+    }
+
+    fn profile(speaker: &Speaker) -> String {
+        format!(
+            "Name: {}\nBackground: {}\nPersonality: {}\nMotivations: {}\nSpeaking style: {}\n\n",
+            speaker.name,
+            speaker.background,
+            speaker.personality,
+            speaker.motivations,
+            speaker.speaking_style
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::CannedBackend;
+
+    fn scores(values: [u8; 5]) -> JudgeScores {
+        JudgeScores {
+            in_character: values[0],
+            goal_achieved: values[1],
+            mood_match: values[2],
+            naturalness: values[3],
+            engagement: values[4],
+        }
+    }
+
+    #[test]
+    fn parses_fenced_verdict() {
+        let reply = "```json\n{\"in_character\": 4, \"goal_achieved\": 3, \"mood_match\": 5, \"naturalness\": 4, \"engagement\": 2, \"critique\": \" Solid. \"}\n```";
+        let (parsed, critique) = parse_verdict(reply).unwrap();
+
+        assert_eq!(parsed, scores([4, 3, 5, 4, 2]));
+        assert_eq!(parsed.overall(), 3.6);
+        assert_eq!(critique, "Solid.");
+    }
+
+    #[test]
+    fn rounds_and_clamps_scores() {
+        let reply = r#"{"in_character": 4.5, "goal_achieved": "3", "mood_match": 9, "naturalness": 0, "engagement": " 2.4 "}"#;
+        let (parsed, critique) = parse_verdict(reply).unwrap();
+
+        assert_eq!(parsed, scores([5, 3, 5, 1, 2]));
+        assert_eq!(critique, "");
+    }
+
+    #[test]
+    fn rejects_missing_or_non_numeric_scores() {
+        assert!(parse_verdict(r#"{"in_character": 4}"#).is_err());
+        let reply = r#"{"in_character": "great", "goal_achieved": 3, "mood_match": 3, "naturalness": 3, "engagement": 3}"#;
+        assert!(
+            parse_verdict(reply)
+                .unwrap_err()
+                .to_string()
+                .contains("in_character")
+        );
+    }
+
+    #[tokio::test]
+    async fn judges_with_any_backend() {
+        let backend = CannedBackend(|system: &str, messages: &[ChatMessage]| {
+            assert!(system.contains("strict script judge"));
+            assert!(messages[0].content.ends_with("Maya: Hello.\n"));
+            Ok(r#"{"in_character": 5, "goal_achieved": 5, "mood_match": 5, "naturalness": 5, "engagement": 5, "critique": "Great."}"#.to_string())
+        });
+        let exchanges = [DialogExchange {
+            speaker: "Maya".to_string(),
+            content: "Hello.".to_string(),
+            stats: None,
+        }];
+
+        let config = DialogConfig::builder()
+            .speaker1(Speaker::new("Maya"))
+            .speaker2(Speaker::new("Dev"))
+            .build()
+            .unwrap();
+
+        let (verdict, _) = DialogJudge::new(backend)
+            .judge(&exchanges, &config)
+            .await
+            .unwrap();
+
+        assert_eq!(verdict.judge_model, "canned");
+        assert_eq!(verdict.overall, 5.0);
+        assert_eq!(verdict.critique, "Great.");
+    }
+}
@@ -0,0 +1,67 @@
+//! Generate dialog between two AI-driven characters using a local LLM.
+//!
+//! Scenes can be loaded from a directory of text files with
+//! [`DialogConfig::load`] or built in code with [`DialogConfig::builder`].
+//! A [`DialogOrchestrator`] runs the scene against any [`ChatBackend`]
+//! ([`OllamaClient`] by default), [`PodcastEditor`] polishes the result and
+//! [`OutputWriter`] saves it. Progress, including streamed tokens, is
+//! reported as [`GenerationEvent`]s to an optional [`GenerationObserver`].
+//!
+//! The `batch`, `server` and `tui` features (all on by default, and needed
+//! by the `dialog-gen` binary) add the `batch` and `compare` runners,
+//! the HTTP API in `server` and the terminal UI in `tui`. The `cli` feature
+//! derives clap's `ValueEnum` for the option enums, which otherwise parse
+//! with `FromStr`. Embedders can turn them all off with
+//! `default-features = false` to leave out clap, glob, axum and ratatui.
+//!
+//! ```no_run
+//! use dialog_gen::{DialogConfig, DialogOrchestrator, Directions, OllamaClient, Speaker};
+//!
+//! # async fn run() -> dialog_gen::Result<()> {
+//! let config = DialogConfig::builder()
+//!     .speaker1(Speaker::new("Maya").with_personality("Enthusiastic, talks fast."))
+//!     .speaker2(Speaker::new("Dev").with_personality("Dry, analytical."))
+//!     .directions(Directions::new("Book Review Episode").with_mood("Playful"))
+//!     .line("Maya", "Okay so I finished it last night.")
+//!     .turns(6)
+//!     .build()?;
+//!
+//! let ollama = OllamaClient::new("http://localhost:11434", "llama3.2:latest");
+//! let dialog = DialogOrchestrator::new(ollama, config).generate().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod analysis;
+pub mod backend;
+#[cfg(feature = "batch")]
+pub mod batch;
+pub mod characters;
+#[cfg(feature = "batch")]
+pub mod compare;
+pub mod config;
+pub mod editor;
+pub mod error;
+pub mod events;
+pub mod fidelity;
+pub mod formats;
+pub mod judge;
+pub mod manifest;
+pub mod ollama;
+pub mod orchestrator;
+pub mod output;
+pub mod pipeline;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod settings;
+#[cfg(feature = "tui")]
+pub mod tui;
+
+pub use backend::ChatBackend;
+pub use config::{DialogConfig, DialogConfigBuilder, DialogLine, Directions, Scene, Speaker};
+pub use editor::PodcastEditor;
+pub use error::{DialogGenError, Result};
+pub use events::{GenerationEvent, GenerationObserver, StderrProgress};
+pub use ollama::{ChatMessage, ChatOptions, ChatResult, ChatStats, OllamaClient};
+pub use orchestrator::{DialogExchange, DialogOrchestrator, GeneratedDialog, GenerationMetadata};
+pub use output::{OutputLayout, OutputWriter};
@@ -1,40 +1,265 @@
-mod config;
-mod editor;
-mod error;
-mod ollama;
-mod orchestrator;
-mod output;
-
 use anyhow::Result;
-use clap::Parser;
-use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
-use config::DialogConfig;
-use editor::PodcastEditor;
-use ollama::OllamaClient;
-use orchestrator::DialogOrchestrator;
-use output::OutputWriter;
+use dialog_gen::editor::EditMode;
+use dialog_gen::fidelity::{FidelityAction, FidelityOptions};
+use dialog_gen::formats::{ExportFormat, ExportOptions};
+use dialog_gen::judge::{DialogJudge, JudgeVerdict};
+use dialog_gen::manifest::{Rerun, RunManifest};
+use dialog_gen::output::SourceMetadata;
+use dialog_gen::pipeline::{self, EditOutcome, EditSettings, EditSource};
+use dialog_gen::server::ServerLimits;
+use dialog_gen::settings::{Settings, SettingsLayer};
+use dialog_gen::{
+    DialogConfig, DialogOrchestrator, GeneratedDialog, GenerationObserver, OllamaClient,
+    OutputLayout, OutputWriter, StderrProgress, analysis, batch, compare, editor, server, tui,
+};
 
 #[derive(Parser)]
 #[command(name = "dialog-gen")]
 #[command(about = "Generate AI-powered dialog between two characters using local LLM")]
 #[command(version)]
+#[command(subcommand_negates_reqs = true)]
 struct Cli {
-    /// Input directory containing configuration files
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate dialog for many scene directories in parallel
+    Batch(BatchArgs),
+
+    /// Run one scene against several models, temperatures and seeds
+    Compare {
+        /// Input directory containing configuration files
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Output directory for the labelled run directories and report
+        /// (defaults to <input>/compare)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Models to compare (repeatable)
+        #[arg(short, long = "model", required = true)]
+        models: Vec<String>,
+
+        /// Temperatures to try with every model (defaults to the resolved temperature)
+        #[arg(short, long = "temperature")]
+        temperatures: Vec<f32>,
+
+        /// Seeds to try with every model and temperature
+        #[arg(short, long = "seed")]
+        seeds: Vec<u64>,
+
+        /// Ollama server URL
+        #[arg(long)]
+        ollama_url: Option<String>,
+
+        /// Number of turns to generate
+        #[arg(long)]
+        turns: Option<usize>,
+
+        /// Verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Compute quality metrics for existing dialog files
+    Stats {
+        /// Dialog files in "SPEAKER: line" format
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Scene directory whose speaker files provide style cues
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+    },
+
+    /// Score a dialog file with a judge model against its scene
+    Judge {
+        /// Dialog file in "SPEAKER: line" format
+        file: PathBuf,
+
+        /// Scene directory the dialog was generated from
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Also write the JSON verdict to this file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Model that judges the dialog. Pick one other than the model that
+        /// wrote it [default: the resolved model]
+        #[arg(long)]
+        judge_model: Option<String>,
+
+        #[command(flatten)]
+        settings: SettingsArgs,
+    },
+
+    /// Repeat a run from its run-manifestN.json
+    Rerun {
+        /// Manifest written by an earlier run
+        manifest: PathBuf,
+
+        /// Output directory (defaults to the manifest's directory)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Ollama server URL [default: the one recorded in the manifest]
+        #[arg(long)]
+        ollama_url: Option<String>,
+
+        /// Verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Run the podcast editor on an existing dialog file
+    Edit {
+        /// Dialog file in "SPEAKER: line" format (e.g. generated-dialog3.txt)
+        file: PathBuf,
+
+        /// Scene directory providing speaker profiles, settings and editors/
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+
+        /// Output directory (defaults to the dialog file's directory)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        #[command(flatten)]
+        settings: SettingsArgs,
+
+        #[command(flatten)]
+        edit: EditArgs,
+
+        #[command(flatten)]
+        export: ExportArgs,
+
+        /// Verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Run a local HTTP API for generating dialog
+    Serve {
+        /// Port to listen on
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+
+        /// Address to bind
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// Most turns a request may ask for
+        #[arg(long, default_value_t = 100)]
+        max_turns: usize,
+
+        /// Most generations to run at once; further requests get 429
+        #[arg(long, default_value_t = 4)]
+        max_running: usize,
+
+        /// Retry a turn that fails or comes back empty up to N times
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        retries: u32,
+
+        /// Seconds to keep finished jobs and their results
+        #[arg(long, default_value_t = 3600)]
+        job_ttl: u64,
+
+        /// Most finished jobs to keep; the oldest are dropped first
+        #[arg(long, default_value_t = 1000)]
+        max_finished_jobs: usize,
+
+        #[command(flatten)]
+        settings: SettingsArgs,
+    },
+
+    /// Inspect the layered configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print each effective setting and where it came from
+    Show {
+        /// Input directory whose scene.txt should be included
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+
+        #[command(flatten)]
+        settings: SettingsArgs,
+    },
+}
+
+#[derive(Args)]
+struct BatchArgs {
+    /// Glob patterns for scene directories (e.g. "demo/prompt*")
+    #[arg(required_unless_present = "manifest")]
+    patterns: Vec<String>,
+
+    /// File listing one scene directory per line
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+
+    /// Maximum number of scenes generated at once
+    #[arg(short, long, default_value_t = 2)]
+    jobs: usize,
+
+    /// Write each scene's output to a subdirectory of this directory
+    /// (defaults to each scene's input directory)
     #[arg(short, long)]
-    input: PathBuf,
+    output: Option<PathBuf>,
+
+    /// How each scene's output files are arranged
+    #[arg(long, value_enum, default_value_t = OutputLayout::Flat)]
+    layout: OutputLayout,
+
+    /// Where to write the summary table
+    #[arg(long, default_value = "batch-summary.txt")]
+    summary: PathBuf,
+
+    /// Retry a turn that fails or comes back empty up to N times
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    retries: u32,
+
+    #[command(flatten)]
+    settings: SettingsArgs,
+}
+
+#[derive(Args)]
+struct GenerateArgs {
+    /// Input directory containing configuration files
+    #[arg(short, long, required = true)]
+    input: Option<PathBuf>,
 
     /// Output directory (defaults to input directory)
     #[arg(short, long)]
     output: Option<PathBuf>,
 
-    /// Ollama model to use (overrides scene.txt)
-    #[arg(short, long, default_value = "mistral:7b")]
-    model: String,
+    /// How output files are arranged: flat numbered files, or a new
+    /// runs/<timestamp>_<model>/ directory per run
+    #[arg(long, value_enum, default_value_t = OutputLayout::Flat)]
+    layout: OutputLayout,
 
-    /// Ollama server URL
-    #[arg(long, default_value = "http://localhost:11434")]
-    ollama_url: String,
+    #[command(flatten)]
+    settings: SettingsArgs,
+
+    /// Retry a turn that fails or comes back empty up to N times
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    retries: u32,
 
     /// Verbose output
     #[arg(short, long)]
@@ -43,29 +268,557 @@ struct Cli {
     /// Apply podcast editor to improve dialog quality
     #[arg(short = 'e', long)]
     edit_podcast: bool,
+
+    #[command(flatten)]
+    edit: EditArgs,
+
+    #[command(flatten)]
+    export: ExportArgs,
+
+    /// Print the assembled prompts for each speaker's first turn and exit
+    /// without contacting Ollama
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Review each turn in a full-screen terminal UI before saving
+    #[arg(long, conflicts_with = "dry_run")]
+    tui: bool,
+}
+
+/// How the podcast editor runs and how its edit is checked
+#[derive(Args)]
+struct EditArgs {
+    /// How the podcast editor revises the dialog
+    #[arg(long, value_enum, default_value_t = EditMode::Script)]
+    edit_mode: EditMode,
+
+    /// Directory of editing passes to run in order [default: <input>/editors]
+    #[arg(long)]
+    editors: Option<PathBuf>,
+
+    /// Keep earlier edits of the same dialog instead of overwriting them
+    /// (later attempts are saved as edited-podcastN-2.txt, -3, ...)
+    #[arg(long)]
+    keep_edits: bool,
+
+    #[command(flatten)]
+    fidelity: FidelityArgs,
+}
+
+/// Extra formats to write the dialog in, and how subtitles are timed
+#[derive(Args)]
+struct ExportArgs {
+    /// Also export the dialog in this format (repeatable; needs the scene's
+    /// --input)
+    #[arg(long = "export", value_enum, requires = "input")]
+    exports: Vec<ExportFormat>,
+
+    /// Gap between turns in subtitle exports, in milliseconds
+    #[arg(long, default_value_t = 300)]
+    subtitle_gap_ms: u64,
+
+    /// TTS manifest with measured line durations for subtitle exports
+    #[arg(long)]
+    tts_manifest: Option<PathBuf>,
+}
+
+/// Limits for how far an edit may stray from the original dialog
+#[derive(Args)]
+struct FidelityArgs {
+    /// Share of original lines (0-1) that must survive editing
+    #[arg(long, default_value_t = 0.8)]
+    fidelity_threshold: f64,
+
+    /// Allowed change in line count, as a fraction of the original
+    #[arg(long, default_value_t = 0.25)]
+    turn_tolerance: f64,
+
+    /// What to do when an edit fails the fidelity checks
+    #[arg(long, value_enum, default_value_t = FidelityAction::Warn)]
+    on_fidelity_fail: FidelityAction,
+}
+
+impl FidelityArgs {
+    fn options(&self) -> FidelityOptions {
+        FidelityOptions {
+            turn_tolerance: self.turn_tolerance,
+            threshold: self.fidelity_threshold,
+            on_fail: self.on_fidelity_fail,
+            ..Default::default()
+        }
+    }
+}
+
+impl ExportArgs {
+    fn options(&self) -> ExportOptions {
+        ExportOptions {
+            subtitle_gap: Duration::from_millis(self.subtitle_gap_ms),
+            tts_manifest: self.tts_manifest.clone(),
+        }
+    }
+}
+
+impl EditArgs {
+    fn settings(&self) -> EditSettings {
+        EditSettings {
+            mode: self.edit_mode,
+            editors: self.editors.clone(),
+            keep_edits: self.keep_edits,
+            fidelity: self.fidelity.options(),
+        }
+    }
+}
+
+/// Settings that can also come from the user config, scene.txt or environment
+#[derive(Args)]
+struct SettingsArgs {
+    /// Ollama model to use [default: mistral:7b]
+    #[arg(short, long)]
+    model: Option<String>,
+
+    /// Ollama server URL [default: http://localhost:11434]
+    #[arg(long)]
+    ollama_url: Option<String>,
+
+    /// Sampling temperature [default: 0.7]
+    #[arg(long)]
+    temperature: Option<f32>,
+
+    /// Number of turns to generate [default: 4]
+    #[arg(long)]
+    turns: Option<usize>,
+}
+
+impl SettingsArgs {
+    fn layer(&self) -> SettingsLayer {
+        SettingsLayer {
+            model: self.model.clone(),
+            ollama_url: self.ollama_url.clone(),
+            temperature: self.temperature,
+            turns: self.turns,
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    // Validate input directory exists
-    if !cli.input.exists() {
-        anyhow::bail!("Input directory does not exist: {}", cli.input.display());
+    match cli.command {
+        Some(Command::Batch(args)) => run_batch(args).await,
+        Some(Command::Compare {
+            input,
+            output,
+            models,
+            temperatures,
+            seeds,
+            ollama_url,
+            turns,
+            verbose,
+        }) => {
+            let layer = SettingsLayer {
+                ollama_url,
+                turns,
+                ..Default::default()
+            };
+            run_compare(
+                &input,
+                output,
+                &models,
+                &temperatures,
+                &seeds,
+                layer,
+                verbose,
+            )
+            .await
+        }
+        Some(Command::Stats { files, input }) => show_stats(&files, input.as_deref()),
+        Some(Command::Judge {
+            file,
+            input,
+            output,
+            judge_model,
+            settings,
+        }) => {
+            run_judge(
+                &file,
+                &input,
+                output.as_deref(),
+                judge_model.as_deref(),
+                &settings,
+            )
+            .await
+        }
+        Some(Command::Rerun {
+            manifest,
+            output,
+            ollama_url,
+            verbose,
+        }) => rerun(&manifest, output, ollama_url, verbose).await,
+        Some(Command::Edit {
+            file,
+            input,
+            output,
+            settings,
+            edit,
+            export,
+            verbose,
+        }) => {
+            run_edit(
+                &file,
+                input.as_deref(),
+                output,
+                &settings,
+                &edit,
+                &export,
+                verbose,
+            )
+            .await
+        }
+        Some(Command::Serve {
+            port,
+            host,
+            max_turns,
+            max_running,
+            retries,
+            job_ttl,
+            max_finished_jobs,
+            settings,
+        }) => {
+            let limits = ServerLimits {
+                max_turns,
+                max_running,
+                max_retries: retries,
+                job_ttl: Duration::from_secs(job_ttl),
+                max_finished_jobs,
+            };
+            serve(&host, port, limits, &settings).await
+        }
+        Some(Command::Config {
+            action: ConfigAction::Show { input, settings },
+        }) => show_config(input.as_deref(), &settings),
+        None => generate(cli.generate).await,
+    }
+}
+
+async fn serve(host: &str, port: u16, limits: ServerLimits, settings: &SettingsArgs) -> Result<()> {
+    let settings = Settings::load(None, settings.layer())?;
+    let listener = tokio::net::TcpListener::bind((host, port))
+        .await
+        .map_err(|e| anyhow::anyhow!("Cannot listen on {}:{}: {}", host, port, e))?;
+
+    eprintln!(
+        "Serving on http://{} (model {}, Ollama at {})",
+        listener.local_addr()?,
+        settings.model.value,
+        settings.ollama_url.value
+    );
+    server::serve(listener, settings, limits).await?;
+    Ok(())
+}
+
+fn validate_input_dir(input: &Path) -> Result<()> {
+    if !input.exists() {
+        anyhow::bail!("Input directory does not exist: {}", input.display());
+    }
+
+    if !input.is_dir() {
+        anyhow::bail!("Input path is not a directory: {}", input.display());
+    }
+
+    Ok(())
+}
+
+fn show_stats(files: &[PathBuf], input: Option<&Path>) -> Result<()> {
+    let speakers = match input {
+        Some(dir) => {
+            validate_input_dir(dir)?;
+            let config = DialogConfig::load(dir)?;
+            vec![config.speaker1, config.speaker2]
+        }
+        None => Vec::new(),
+    };
+
+    for (i, file) in files.iter().enumerate() {
+        let script = std::fs::read_to_string(file)
+            .map_err(|e| anyhow::anyhow!("{}: {}", file.display(), e))?;
+        let exchanges = editor::parse_script(&script);
+
+        if i > 0 {
+            println!();
+        }
+        println!("{}", file.display());
+        print!("{}", analysis::analyze(&exchanges, &speakers).format());
+    }
+
+    Ok(())
+}
+
+async fn run_judge(
+    file: &Path,
+    input: &Path,
+    output: Option<&Path>,
+    judge_model: Option<&str>,
+    args: &SettingsArgs,
+) -> Result<()> {
+    validate_input_dir(input)?;
+
+    let config = DialogConfig::load(input)?;
+    let settings = Settings::load(Some(input), args.layer())?;
+
+    let script =
+        std::fs::read_to_string(file).map_err(|e| anyhow::anyhow!("{}: {}", file.display(), e))?;
+    let exchanges = editor::parse_script(&script);
+    if exchanges.is_empty() {
+        anyhow::bail!("No dialog lines found in {}", file.display());
+    }
+
+    // The dialog's own metadata says which model wrote it, when it has any
+    let generated_by = SourceMetadata::read(file)
+        .model
+        .unwrap_or_else(|| settings.model.value.clone());
+    let model = judge_model.unwrap_or(&settings.model.value);
+    if model == generated_by {
+        eprintln!(
+            "Note: {} is judging its own dialog; pass --judge-model to use another model",
+            model
+        );
+    }
+
+    let ollama = OllamaClient::new(&settings.ollama_url.value, model);
+    if !ollama.health_check().await? {
+        anyhow::bail!(
+            "Ollama server not available at {}. Is Ollama running?",
+            settings.ollama_url.value
+        );
+    }
+
+    let (verdict, _) = DialogJudge::new(ollama).judge(&exchanges, &config).await?;
+
+    #[derive(serde::Serialize)]
+    struct Report<'a> {
+        dialog_file: String,
+        #[serde(flatten)]
+        verdict: &'a JudgeVerdict,
+    }
+
+    let json = serde_json::to_string_pretty(&Report {
+        dialog_file: file.display().to_string(),
+        verdict: &verdict,
+    })?;
+
+    if let Some(path) = output {
+        std::fs::write(path, &json)?;
+    }
+    println!("{}", json);
+
+    Ok(())
+}
+
+fn show_config(input: Option<&Path>, args: &SettingsArgs) -> Result<()> {
+    if let Some(dir) = input {
+        validate_input_dir(dir)?;
+    }
+
+    let settings = Settings::load(input, args.layer())?;
+
+    match Settings::user_config_path() {
+        Some(path) => println!("User config: {}", path.display()),
+        None => println!("User config: (no home directory)"),
+    }
+    println!();
+    println!("{:<12} {:<32} Source", "Setting", "Value");
+    println!(
+        "{:<12} {:<32} {}",
+        "model", settings.model.value, settings.model.source
+    );
+    println!(
+        "{:<12} {:<32} {}",
+        "ollama_url", settings.ollama_url.value, settings.ollama_url.source
+    );
+    println!(
+        "{:<12} {:<32} {}",
+        "temperature", settings.temperature.value, settings.temperature.source
+    );
+    println!(
+        "{:<12} {:<32} {}",
+        "turns", settings.turns.value, settings.turns.source
+    );
+
+    Ok(())
+}
+
+async fn run_batch(args: BatchArgs) -> Result<()> {
+    let mut dirs = batch::expand_patterns(&args.patterns)?;
+    if let Some(path) = &args.manifest {
+        dirs.extend(batch::read_manifest(path)?);
+    }
+    let dirs = batch::dedupe(dirs);
+
+    if dirs.is_empty() {
+        anyhow::bail!("No scene directories matched");
+    }
+
+    eprintln!(
+        "Generating {} scenes ({} at a time)...\n",
+        dirs.len(),
+        args.jobs
+    );
+
+    let outcomes = batch::run(
+        dirs,
+        args.settings.layer(),
+        args.output,
+        args.layout,
+        args.jobs,
+        args.retries,
+        |outcome| match &outcome.result {
+            Ok(success) => {
+                eprintln!(
+                    "[ok]     {} -> {}",
+                    outcome.input_dir.display(),
+                    success.output_path.display()
+                );
+                if let Some(e) = &success.latest_link_error {
+                    eprintln!("         Warning: {}", e);
+                }
+            }
+            Err(e) => eprintln!("[failed] {}: {}", outcome.input_dir.display(), e),
+        },
+    )
+    .await;
+    let summary = batch::format_summary(&outcomes);
+    std::fs::write(&args.summary, &summary)?;
+
+    println!("\n{}", summary);
+    println!("Summary written: {}", args.summary.display());
+
+    let failed = outcomes.iter().filter(|o| o.result.is_err()).count();
+    if failed > 0 {
+        anyhow::bail!("{} of {} scenes failed", failed, outcomes.len());
+    }
+
+    Ok(())
+}
+
+async fn run_compare(
+    input: &Path,
+    output: Option<PathBuf>,
+    models: &[String],
+    temperatures: &[f32],
+    seeds: &[u64],
+    layer: SettingsLayer,
+    verbose: bool,
+) -> Result<()> {
+    validate_input_dir(input)?;
+
+    let mut config = DialogConfig::load(input)?;
+    let settings = Settings::load(Some(input), layer)?;
+    settings.apply(&mut config);
+
+    let ollama = OllamaClient::new(&settings.ollama_url.value, &settings.model.value);
+    if !ollama.health_check().await? {
+        anyhow::bail!(
+            "Ollama server not available at {}. Is Ollama running?",
+            settings.ollama_url.value
+        );
+    }
+
+    let temperatures = if temperatures.is_empty() {
+        vec![config.scene.temperature]
+    } else {
+        temperatures.to_vec()
+    };
+    let seeds: Vec<Option<u64>> = if seeds.is_empty() {
+        vec![config.scene.seed]
+    } else {
+        seeds.iter().copied().map(Some).collect()
+    };
+
+    let specs = compare::expand(models, &temperatures, &seeds);
+    let output_root = output.unwrap_or_else(|| input.join("compare"));
+    eprintln!(
+        "Running {} comparisons into {}\n",
+        specs.len(),
+        output_root.display()
+    );
+
+    let observer: Option<Arc<dyn GenerationObserver>> = if verbose {
+        Some(Arc::new(StderrProgress))
+    } else {
+        None
+    };
+    let outcomes = compare::run(
+        &config,
+        input,
+        &settings.ollama_url.value,
+        specs,
+        &output_root,
+        observer,
+        |outcome| match &outcome.result {
+            Ok((_, path)) => eprintln!("[ok]     {} -> {}", outcome.spec.label(), path.display()),
+            Err(e) => eprintln!("[failed] {}: {}", outcome.spec.label(), e),
+        },
+    )
+    .await;
+
+    std::fs::create_dir_all(&output_root)?;
+    let report_path = output_root.join("comparison-report.md");
+    std::fs::write(&report_path, compare::format_report(&config, &outcomes))?;
+    println!("\nComparison report: {}", report_path.display());
+
+    let failed = outcomes.iter().filter(|o| o.result.is_err()).count();
+    if failed > 0 {
+        anyhow::bail!("{} of {} runs failed", failed, outcomes.len());
     }
 
-    if !cli.input.is_dir() {
-        anyhow::bail!("Input path is not a directory: {}", cli.input.display());
+    Ok(())
+}
+
+fn print_dry_run(orchestrator: &DialogOrchestrator, settings: &Settings) {
+    println!(
+        "Model: {} ({})\nTemperature: {} ({})\nTurns: {} ({})",
+        settings.model.value,
+        settings.model.source,
+        settings.temperature.value,
+        settings.temperature.source,
+        settings.turns.value,
+        settings.turns.source,
+    );
+
+    for (i, preview) in orchestrator.preview_first_turns().iter().enumerate() {
+        println!(
+            "\n=== Turn {}: {} (system + {} messages, ~{} tokens) ===",
+            i + 1,
+            preview.speaker,
+            preview.messages.len(),
+            preview.estimated_tokens()
+        );
+        println!("--- system ---\n{}", preview.system_prompt);
+        for message in &preview.messages {
+            println!("--- {} ---\n{}", message.role, message.content);
+        }
     }
+}
+
+async fn generate(args: GenerateArgs) -> Result<()> {
+    let input = args.input.expect("clap requires --input");
+
+    // Validate input directory exists
+    validate_input_dir(&input)?;
 
     // Load configuration
-    if cli.verbose {
-        eprintln!("Loading configuration from: {}", cli.input.display());
+    if args.verbose {
+        eprintln!("Loading configuration from: {}", input.display());
     }
 
-    let config = DialogConfig::load(&cli.input)?;
+    let mut config = DialogConfig::load(&input)?;
+
+    // Resolve model, turns and temperature across all configuration layers
+    let settings = Settings::load(Some(&input), args.settings.layer())?;
+    settings.apply(&mut config);
 
-    if cli.verbose {
+    if args.verbose {
         eprintln!(
             "Loaded speakers: {} and {}",
             config.speaker1.name, config.speaker2.name
@@ -76,45 +829,84 @@ async fn main() -> Result<()> {
         );
     }
 
-    // Determine model (CLI override > scene.txt > default)
-    let model = if cli.model != "mistral:7b" {
-        cli.model.clone()
-    } else {
-        config.scene.model.clone().unwrap_or(cli.model.clone())
-    };
+    let model = &settings.model.value;
+    let ollama_url = &settings.ollama_url.value;
 
     // Create Ollama client
-    let ollama = OllamaClient::new(&cli.ollama_url, &model);
+    let ollama = OllamaClient::new(ollama_url, model);
+
+    if args.dry_run {
+        print_dry_run(&DialogOrchestrator::new(ollama, config), &settings);
+        return Ok(());
+    }
 
     // Check Ollama server availability
-    if cli.verbose {
+    if args.verbose {
         eprintln!(
-            "Connecting to Ollama at {} with model {}...",
-            cli.ollama_url, model
+            "Connecting to Ollama at {} with model {} (from {})...",
+            ollama_url, model, settings.model.source
         );
     }
 
     if !ollama.health_check().await? {
         anyhow::bail!(
             "Ollama server not available at {}. Is Ollama running?",
-            cli.ollama_url
+            ollama_url
         );
     }
 
-    if cli.verbose {
+    if args.verbose {
         eprintln!("Ollama server connected.\n");
     }
 
     // Create orchestrator and generate dialog
-    let orchestrator = DialogOrchestrator::new(ollama.clone(), config);
-    let dialog = orchestrator.generate(cli.verbose).await?;
+    let progress: Option<Arc<dyn GenerationObserver>> = if args.verbose {
+        Some(Arc::new(StderrProgress))
+    } else {
+        None
+    };
+
+    let new_orchestrator =
+        || DialogOrchestrator::new(ollama.clone(), config.clone()).with_max_retries(args.retries);
+    let mut orchestrator = new_orchestrator();
+
+    let dialog = if args.tui {
+        match tui::run(new_orchestrator()).await? {
+            Some(dialog) => dialog,
+            None => {
+                println!("Session discarded.");
+                return Ok(());
+            }
+        }
+    } else {
+        if let Some(progress) = &progress {
+            orchestrator = orchestrator.with_observer(progress.clone());
+        }
+        orchestrator.generate().await?
+    };
 
     // Write output
-    let output_dir = cli.output.unwrap_or_else(|| cli.input.clone());
-    let writer = OutputWriter::new(output_dir);
+    let output_dir = args.output.unwrap_or_else(|| input.clone());
+    let writer = OutputWriter::with_layout(output_dir, args.layout, model)?;
+    if let Some(e) = writer.latest_link_error() {
+        eprintln!("Warning: {}", e);
+    }
     let output_path = writer.write(&dialog)?;
+    let mut manifest = RunManifest::capture(&orchestrator, Some(&input)).await?;
+    manifest.interactive = args.tui;
+    let manifest_path = writer.write_manifest(&output_path, &manifest)?;
+
+    let export_options = args.export.options();
 
     println!("\nDialog generated: {}", output_path.display());
+    println!("Run manifest: {}", manifest_path.display());
+
+    for format in &args.export.exports {
+        let export_path =
+            writer.write_export(&output_path, *format, &dialog, &config, &export_options)?;
+        println!("Exported: {}", export_path.display());
+    }
+
     println!("\n--- Generated Dialog ---\n");
 
     for exchange in &dialog.exchanges {
@@ -122,18 +914,177 @@ async fn main() -> Result<()> {
     }
 
     // Optional podcast editing step
-    if cli.edit_podcast {
-        let editor = PodcastEditor::new(ollama);
-        let edited = editor.edit(&dialog, cli.verbose).await?;
+    if args.edit_podcast {
+        let speakers = vec![config.speaker1.clone(), config.speaker2.clone()];
+        let source = EditSource {
+            dialog: &dialog,
+            path: &output_path,
+            speakers: &speakers,
+            scene_dir: Some(&input),
+        };
+        let edit =
+            pipeline::edit_and_save(source, &ollama, &args.edit.settings(), &writer, progress)
+                .await?;
+
+        for format in &args.export.exports {
+            let export_path =
+                writer.write_export(&edit.path, *format, &edit.dialog, &config, &export_options)?;
+            println!("Exported: {}", export_path.display());
+        }
+
+        print_edit(&edit)?;
+    }
+
+    Ok(())
+}
+
+async fn rerun(
+    manifest_path: &Path,
+    output: Option<PathBuf>,
+    ollama_url: Option<String>,
+    verbose: bool,
+) -> Result<()> {
+    let mut rerun = Rerun::prepare(manifest_path, ollama_url.as_deref()).await?;
+    for note in rerun.notes() {
+        eprintln!("Note: {}", note);
+    }
+    if verbose {
+        rerun = rerun.with_observer(Arc::new(StderrProgress));
+    }
 
-        let edited_path = writer.write_edited(&edited)?;
+    let output_dir = output.unwrap_or_else(|| {
+        manifest_path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf()
+    });
+    let outcome = rerun.run(&OutputWriter::new(output_dir)).await?;
 
-        println!("\n--- Edited Podcast ---\n");
-        for exchange in &edited.exchanges {
-            println!("{}: {}\n", exchange.speaker, exchange.content);
+    println!("\nDialog generated: {}", outcome.output_path.display());
+    println!("Run manifest: {}", outcome.manifest_path.display());
+    println!("\n--- Generated Dialog ---\n");
+    for exchange in &outcome.dialog.exchanges {
+        println!("{}: {}\n", exchange.speaker, exchange.content);
+    }
+
+    Ok(())
+}
+
+/// Print an edit and its fidelity check, failing if asked to
+fn print_edit(edit: &EditOutcome) -> Result<()> {
+    for (i, (name, path)) in edit.passes.iter().enumerate() {
+        println!("Edit pass {} ({}): {}", i + 1, name, path.display());
+    }
+    println!("\n--- Edited Podcast ---\n");
+    for exchange in &edit.dialog.exchanges {
+        println!("{}: {}\n", exchange.speaker, exchange.content);
+    }
+    for pass in &edit.dialog.metadata.edit_passes {
+        for dropped in &pass.dropped_edits {
+            eprintln!("Warning: {} dropped an edit: {}", pass.name, dropped);
         }
-        println!("Edited podcast: {}", edited_path.display());
     }
+    println!("Edited podcast: {}", edit.path.display());
+    println!(
+        "Edit diff: {} (fidelity {:.0}%)",
+        edit.diff_path.display(),
+        edit.report.score * 100.0
+    );
 
+    if let Some(warning) = edit.report.enforce(&edit.diff_path)? {
+        eprintln!("Warning: {}", warning);
+    }
     Ok(())
 }
+
+async fn run_edit(
+    file: &Path,
+    input: Option<&Path>,
+    output: Option<PathBuf>,
+    settings_args: &SettingsArgs,
+    args: &EditArgs,
+    export: &ExportArgs,
+    verbose: bool,
+) -> Result<()> {
+    let config = match input {
+        Some(dir) => {
+            validate_input_dir(dir)?;
+            Some(DialogConfig::load(dir)?)
+        }
+        None => None,
+    };
+    let settings = Settings::load(input, settings_args.layer())?;
+
+    let script =
+        std::fs::read_to_string(file).map_err(|e| anyhow::anyhow!("{}: {}", file.display(), e))?;
+    let exchanges = editor::parse_script(&script);
+    if exchanges.is_empty() {
+        anyhow::bail!("No dialog lines found in {}", file.display());
+    }
+
+    // Without a scene, the speakers are whoever appears in the script
+    let speakers = match &config {
+        Some(config) => vec![config.speaker1.clone(), config.speaker2.clone()],
+        None => editor::script_speakers(&exchanges),
+    };
+
+    // A generated dialog's metadata file says which model wrote it
+    let source = SourceMetadata::read(file);
+    let dialog = GeneratedDialog::from_script(
+        exchanges,
+        source.model.unwrap_or_else(|| "unknown".to_string()),
+        source.temperature.unwrap_or(settings.temperature.value),
+        &speakers,
+    );
+
+    let ollama = OllamaClient::new(&settings.ollama_url.value, &settings.model.value);
+    if verbose {
+        eprintln!(
+            "Editing {} ({} lines) with {} at {}...",
+            file.display(),
+            dialog.exchanges.len(),
+            settings.model.value,
+            settings.ollama_url.value
+        );
+    }
+    if !ollama.health_check().await? {
+        anyhow::bail!(
+            "Ollama server not available at {}. Is Ollama running?",
+            settings.ollama_url.value
+        );
+    }
+
+    let progress: Option<Arc<dyn GenerationObserver>> = if verbose {
+        Some(Arc::new(StderrProgress))
+    } else {
+        None
+    };
+
+    let output_dir = output.unwrap_or_else(|| {
+        file.parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf()
+    });
+    let writer = OutputWriter::new(output_dir);
+    let source = EditSource {
+        dialog: &dialog,
+        path: file,
+        speakers: &speakers,
+        scene_dir: input,
+    };
+    let edit =
+        pipeline::edit_and_save(source, &ollama, &args.settings(), &writer, progress).await?;
+
+    if let Some(config) = &config {
+        let export_options = export.options();
+        for format in &export.exports {
+            let export_path =
+                writer.write_export(&edit.path, *format, &edit.dialog, config, &export_options)?;
+            println!("Exported: {}", export_path.display());
+        }
+    }
+
+    print_edit(&edit)
+}
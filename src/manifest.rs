@@ -0,0 +1,412 @@
+//! Run manifests
+//!
+//! Every run writes `run-manifestN.json` next to `generated-dialogN.txt`.
+//! It records what produced the dialog: the fully resolved scene, a SHA-256
+//! of each input file (or that it was absent), the system prompt template, the sampling options and
+//! seed, the model and its Ollama digest, the tool version and a timestamp.
+//! [`Rerun`] (`dialog-gen rerun`) repeats a run from the manifest alone.
+
+use crate::characters;
+use crate::config::DialogConfig;
+use crate::error::{DialogGenError, Result};
+use crate::events::GenerationObserver;
+use crate::ollama::{ChatOptions, OllamaClient};
+use crate::orchestrator::{DialogOrchestrator, GeneratedDialog};
+use crate::output::OutputWriter;
+use crate::settings::Settings;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Scene files `DialogConfig::load` and the settings layers read
+const INPUT_FILES: &[&str] = &[
+    "speaker1.txt",
+    "speaker2.txt",
+    "directions.txt",
+    "prompt.txt",
+    "prompt.fountain",
+    "scene.txt",
+];
+
+/// Everything needed to repeat a generation run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunManifest {
+    /// dialog-gen version that wrote the manifest
+    pub tool_version: String,
+    /// When the run finished (RFC 3339)
+    pub created_at: String,
+    pub model: String,
+    /// Content digest Ollama reports for the model, when it reports one
+    pub model_digest: Option<String>,
+    pub ollama_url: String,
+    /// Sampling options sent with every turn
+    pub options: ChatOptions,
+    /// Retries allowed for a failed or empty turn
+    #[serde(default)]
+    pub max_retries: u32,
+    /// Scene directory the run was loaded from
+    pub input_dir: Option<PathBuf>,
+    /// `sha256:<hex>` of each input file, or None for one that didn't
+    /// exist: scene files by name, library characters and the user config
+    /// by path
+    pub input_hashes: BTreeMap<String, Option<String>>,
+    pub system_prompt_template: String,
+    /// The scene after every settings layer was applied
+    pub config: DialogConfig,
+    /// Manifest this run was repeated from, for `rerun` output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerun_of: Option<PathBuf>,
+    /// The dialog was steered in the TUI (director notes, inline edits,
+    /// regenerated lines), which the manifest doesn't record, so it can't be
+    /// rerun
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interactive: bool,
+}
+
+impl RunManifest {
+    /// Describe a run of `orchestrator`. The model digest is looked up on
+    /// the server; a server that can't report one leaves it empty.
+    pub async fn capture(
+        orchestrator: &DialogOrchestrator<OllamaClient>,
+        input_dir: Option<&Path>,
+    ) -> Result<Self> {
+        let ollama = orchestrator.backend();
+        let input_hashes = match input_dir {
+            Some(dir) => hash_inputs(dir)?,
+            None => BTreeMap::new(),
+        };
+
+        Ok(Self {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: chrono::Local::now().to_rfc3339(),
+            model: ollama.model().to_string(),
+            model_digest: ollama.model_digest().await.ok().flatten(),
+            ollama_url: ollama.base_url().to_string(),
+            options: orchestrator.chat_options(),
+            max_retries: orchestrator.max_retries(),
+            input_dir: input_dir
+                .map(|dir| dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())),
+            input_hashes,
+            system_prompt_template: orchestrator.system_prompt_template().to_string(),
+            config: orchestrator.config().clone(),
+            rerun_of: None,
+            interactive: false,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Input files that differ from when the manifest was written, that no
+    /// longer exist or that have appeared since. Empty when the scene
+    /// directory is gone entirely.
+    pub fn changed_inputs(&self) -> Vec<String> {
+        let Some(dir) = self.input_dir.as_deref().filter(|dir| dir.is_dir()) else {
+            return Vec::new();
+        };
+
+        self.input_hashes
+            .iter()
+            .filter(|(name, hash)| hash_if_present(&dir.join(name)).ok().as_ref() != Some(*hash))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+/// A run about to be repeated from its manifest
+pub struct Rerun {
+    manifest: RunManifest,
+    manifest_path: PathBuf,
+    orchestrator: DialogOrchestrator,
+    notes: Vec<String>,
+}
+
+/// A repeated run, written next to its new manifest
+pub struct RerunOutcome {
+    pub dialog: GeneratedDialog,
+    pub output_path: PathBuf,
+    pub manifest_path: PathBuf,
+}
+
+impl Rerun {
+    /// Load the manifest and connect to Ollama, at `ollama_url` or the
+    /// server the run used. Interactive (`--tui`) sessions can't be rerun.
+    pub async fn prepare(manifest_path: &Path, ollama_url: Option<&str>) -> Result<Self> {
+        let manifest = RunManifest::load(manifest_path).map_err(|e| {
+            DialogGenError::InvalidConfig(format!("{}: {}", manifest_path.display(), e))
+        })?;
+        if manifest.interactive {
+            return Err(DialogGenError::InvalidConfig(format!(
+                "{} records an interactive (--tui) session, which can't be rerun",
+                manifest_path.display()
+            )));
+        }
+
+        let mut notes = Vec::new();
+        let changed = manifest.changed_inputs();
+        if !changed.is_empty() {
+            notes.push(format!(
+                "{} changed since the run; using the scene recorded in the manifest",
+                changed.join(", ")
+            ));
+        }
+        if manifest.options.seed.is_none() {
+            notes.push("the run had no fixed seed, so the dialog will differ".to_string());
+        }
+
+        let ollama_url = ollama_url.unwrap_or(&manifest.ollama_url);
+        let ollama = OllamaClient::new(ollama_url, &manifest.model);
+        if !ollama.health_check().await? {
+            return Err(DialogGenError::OllamaUnavailable(ollama_url.to_string()));
+        }
+
+        let digest = ollama.model_digest().await.ok().flatten();
+        if manifest.model_digest.is_some() && digest != manifest.model_digest {
+            notes.push(format!(
+                "{} is now {} (the run used {})",
+                manifest.model,
+                digest.as_deref().unwrap_or("unknown"),
+                manifest.model_digest.as_deref().unwrap_or("unknown")
+            ));
+        }
+
+        let mut config = manifest.config.clone();
+        config.scene.temperature = manifest.options.temperature;
+        config.scene.seed = manifest.options.seed;
+        let orchestrator = DialogOrchestrator::new(ollama, config)
+            .with_system_prompt_template(&manifest.system_prompt_template)
+            .with_max_retries(manifest.max_retries);
+
+        Ok(Self {
+            manifest,
+            manifest_path: manifest_path.to_path_buf(),
+            orchestrator,
+            notes,
+        })
+    }
+
+    /// Ways the rerun may differ from the original: changed scene files, no
+    /// fixed seed, a different model digest
+    pub fn notes(&self) -> &[String] {
+        &self.notes
+    }
+
+    /// Report progress events to `observer`
+    pub fn with_observer(mut self, observer: Arc<dyn GenerationObserver>) -> Self {
+        self.orchestrator = self.orchestrator.with_observer(observer);
+        self
+    }
+
+    /// Generate the dialog again and write it with a manifest pointing back
+    /// at the original
+    pub async fn run(self, writer: &OutputWriter) -> Result<RerunOutcome> {
+        let dialog = self.orchestrator.generate().await?;
+        let output_path = writer.write(&dialog)?;
+
+        // The rerun's inputs are the original run's, by way of the manifest
+        let mut manifest = RunManifest::capture(&self.orchestrator, None).await?;
+        manifest.input_dir = self.manifest.input_dir;
+        manifest.input_hashes = self.manifest.input_hashes;
+        manifest.rerun_of = Some(self.manifest_path);
+        let manifest_path = writer.write_manifest(&output_path, &manifest)?;
+
+        Ok(RerunOutcome {
+            dialog,
+            output_path,
+            manifest_path,
+        })
+    }
+}
+
+/// Hash every scene file `dir` may have, the library characters its
+/// speakers use and the user config file (both keyed by full path). Files
+/// that don't exist are recorded as None so their appearance is noticed.
+pub fn hash_inputs(dir: &Path) -> Result<BTreeMap<String, Option<String>>> {
+    let mut hashes = BTreeMap::new();
+    for name in INPUT_FILES {
+        hashes.insert(name.to_string(), hash_if_present(&dir.join(name))?);
+    }
+    for path in characters::referenced(dir)? {
+        hashes.insert(path.display().to_string(), Some(hash_file(&path)?));
+    }
+    if let Some(path) = Settings::user_config_path() {
+        hashes.insert(path.display().to_string(), hash_if_present(&path)?);
+    }
+    Ok(hashes)
+}
+
+/// Hash of a file, or None when there is no file at `path`
+fn hash_if_present(path: &Path) -> Result<Option<String>> {
+    if path.is_file() {
+        hash_file(path).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// `sha256:<hex>` of a file's contents
+pub fn hash_file(path: &Path) -> Result<String> {
+    let bytes = fs::read(path)?;
+    Ok(format!("sha256:{:x}", Sha256::digest(&bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Speaker;
+    use std::process;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("dialog-gen-test-{}-{}", process::id(), name));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn manifest(input_dir: Option<&Path>) -> RunManifest {
+        let config = DialogConfig::builder()
+            .speaker1(Speaker::new("Maya"))
+            .speaker2(Speaker::new("Dev"))
+            .build()
+            .unwrap();
+        RunManifest {
+            tool_version: "0.1.0".to_string(),
+            created_at: "2026-01-01T00:00:00+00:00".to_string(),
+            model: "llama3.2:latest".to_string(),
+            model_digest: None,
+            ollama_url: "http://127.0.0.1:9".to_string(),
+            options: ChatOptions {
+                temperature: 0.7,
+                seed: Some(42),
+            },
+            max_retries: 0,
+            input_dir: input_dir.map(Path::to_path_buf),
+            input_hashes: match input_dir {
+                Some(dir) => hash_inputs(dir).unwrap(),
+                None => BTreeMap::new(),
+            },
+            system_prompt_template: "You are {speaker}.".to_string(),
+            config,
+            rerun_of: None,
+            interactive: false,
+        }
+    }
+
+    #[test]
+    fn absent_scene_files_are_recorded() {
+        let dir = TempDir::new("manifest-absent");
+        fs::write(dir.0.join("speaker1.txt"), "Name: Maya").unwrap();
+
+        let hashes = hash_inputs(&dir.0).unwrap();
+        assert!(
+            hashes["speaker1.txt"]
+                .as_deref()
+                .unwrap()
+                .starts_with("sha256:")
+        );
+        assert_eq!(hashes["prompt.fountain"], None);
+        assert!(INPUT_FILES.iter().all(|name| hashes.contains_key(*name)));
+    }
+
+    #[test]
+    fn changed_inputs_reports_edited_removed_and_added_files() {
+        let dir = TempDir::new("manifest-changed");
+        fs::write(dir.0.join("speaker1.txt"), "Name: Maya").unwrap();
+        fs::write(dir.0.join("speaker2.txt"), "Name: Dev").unwrap();
+        fs::write(dir.0.join("prompt.txt"), "Maya: Hi.").unwrap();
+        let manifest = manifest(Some(&dir.0));
+        assert!(manifest.changed_inputs().is_empty());
+
+        fs::write(dir.0.join("speaker1.txt"), "Name: Maya\nPersonality: Loud.").unwrap();
+        fs::remove_file(dir.0.join("prompt.txt")).unwrap();
+        fs::write(dir.0.join("prompt.fountain"), "MAYA\nHi.\n").unwrap();
+
+        assert_eq!(
+            manifest.changed_inputs(),
+            ["prompt.fountain", "prompt.txt", "speaker1.txt"]
+        );
+    }
+
+    #[test]
+    fn changed_inputs_is_empty_once_the_scene_is_gone() {
+        let dir = TempDir::new("manifest-gone");
+        fs::write(dir.0.join("speaker1.txt"), "Name: Maya").unwrap();
+        let manifest = manifest(Some(&dir.0));
+        drop(dir);
+
+        assert!(manifest.changed_inputs().is_empty());
+    }
+
+    #[test]
+    fn manifests_round_trip_and_older_hashes_still_load() {
+        let dir = TempDir::new("manifest-json");
+        let mut manifest = manifest(None);
+        manifest.input_hashes = BTreeMap::from([
+            ("speaker1.txt".to_string(), Some("sha256:abc".to_string())),
+            ("scene.txt".to_string(), None),
+        ]);
+        let path = dir.0.join("run-manifest1.json");
+        fs::write(&path, manifest.to_json().unwrap()).unwrap();
+
+        let loaded = RunManifest::load(&path).unwrap();
+        assert_eq!(loaded.input_hashes, manifest.input_hashes);
+        assert_eq!(loaded.options.seed, Some(42));
+        assert!(!manifest.to_json().unwrap().contains("interactive"));
+
+        // Manifests written before absent files were recorded
+        let mut json: serde_json::Value =
+            serde_json::from_str(&manifest.to_json().unwrap()).unwrap();
+        json["input_hashes"] = serde_json::json!({"speaker1.txt": "sha256:abc"});
+        json.as_object_mut().unwrap().remove("max_retries");
+        fs::write(&path, json.to_string()).unwrap();
+        let loaded = RunManifest::load(&path).unwrap();
+        assert_eq!(
+            loaded.input_hashes["speaker1.txt"].as_deref(),
+            Some("sha256:abc")
+        );
+        assert_eq!(loaded.max_retries, 0);
+    }
+
+    #[tokio::test]
+    async fn interactive_sessions_are_not_rerun() {
+        let dir = TempDir::new("manifest-interactive");
+        let mut manifest = manifest(None);
+        manifest.interactive = true;
+        let path = dir.0.join("run-manifest1.json");
+        fs::write(&path, manifest.to_json().unwrap()).unwrap();
+
+        let error = Rerun::prepare(&path, None).await.err().unwrap();
+        assert!(matches!(error, DialogGenError::InvalidConfig(_)));
+        assert!(error.to_string().contains("interactive (--tui) session"));
+    }
+
+    #[tokio::test]
+    async fn unreadable_manifests_name_the_file() {
+        let dir = TempDir::new("manifest-unreadable");
+        let path = dir.0.join("run-manifest1.json");
+        fs::write(&path, "{\"model\": ").unwrap();
+
+        let error = Rerun::prepare(&path, None).await.err().unwrap();
+        assert!(error.to_string().contains("run-manifest1.json"));
+    }
+}
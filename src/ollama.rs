@@ -21,11 +21,18 @@ struct ChatRequest {
     messages: Vec<ChatMessage>,
     stream: bool,
     options: ChatOptions,
+    /// "json" constrains the reply to valid JSON
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'static str>,
 }
 
-#[derive(Serialize)]
-struct ChatOptions {
-    temperature: f32,
+/// Sampling options sent with each chat request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatOptions {
+    pub temperature: f32,
+    /// Fixed seed for reproducible sampling
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
 }
 
 #[derive(Deserialize)]
@@ -39,16 +46,33 @@ struct ChatResponse {
     prompt_eval_count: Option<u64>,
 }
 
+/// One line of a streamed `/api/chat` response
+#[derive(Deserialize)]
+struct ChatStreamChunk {
+    #[serde(default)]
+    message: Option<ChatMessage>,
+    #[serde(default)]
+    done: bool,
+    #[serde(default)]
+    eval_count: Option<u64>,
+    #[serde(default)]
+    eval_duration: Option<u64>,
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+}
+
 /// Statistics from a single LLM call
 #[derive(Debug, Clone, Default)]
 pub struct ChatStats {
+    pub model: String,
     pub prompt_tokens: u64,
     pub completion_tokens: u64,
     pub eval_duration_ns: u64,
     pub wall_time: Duration,
+    /// Failed attempts before this call succeeded
+    pub retries: u32,
 }
 
-#[allow(dead_code)]
 impl ChatStats {
     pub fn tokens_per_second(&self) -> f64 {
         if self.eval_duration_ns == 0 {
@@ -78,6 +102,19 @@ impl OllamaClient {
         &self.model
     }
 
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// A client for the same server using a different model
+    pub fn with_model(&self, model: &str) -> Self {
+        Self {
+            client: self.client.clone(),
+            base_url: self.base_url.clone(),
+            model: model.to_string(),
+        }
+    }
+
     /// Check if Ollama server is available
     pub async fn health_check(&self) -> Result<bool> {
         let url = format!("{}/api/tags", self.base_url);
@@ -87,40 +124,180 @@ impl OllamaClient {
         }
     }
 
+    /// Content digest of the model (e.g. "sha256:..."), from `/api/show`
+    /// or, for servers that don't report it there, the `/api/tags` listing
+    pub async fn model_digest(&self) -> Result<Option<String>> {
+        #[derive(Deserialize)]
+        struct Show {
+            #[serde(default)]
+            digest: Option<String>,
+        }
+        #[derive(Deserialize)]
+        struct Tags {
+            #[serde(default)]
+            models: Vec<TagEntry>,
+        }
+        #[derive(Deserialize)]
+        struct TagEntry {
+            name: String,
+            #[serde(default)]
+            digest: Option<String>,
+        }
+
+        let url = format!("{}/api/show", self.base_url);
+        let response = self
+            .client
+            .post(&url)
+            .json(&serde_json::json!({ "model": self.model }))
+            .send()
+            .await
+            .map_err(|e| DialogGenError::OllamaUnavailable(e.to_string()))?;
+        if response.status().is_success()
+            && let Some(digest) = response.json::<Show>().await?.digest
+        {
+            return Ok(Some(digest));
+        }
+
+        let url = format!("{}/api/tags", self.base_url);
+        let tags: Tags = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| DialogGenError::OllamaUnavailable(e.to_string()))?
+            .json()
+            .await?;
+        Ok(tags
+            .models
+            .into_iter()
+            .find(|m| {
+                // Ollama lists an untagged model under its `:latest` tag
+                m.name == self.model
+                    || (!self.model.contains(':')
+                        && m.name.strip_suffix(":latest") == Some(self.model.as_str()))
+            })
+            .and_then(|m| m.digest))
+    }
+
     /// Generate a response using the chat API
     pub async fn chat(
         &self,
         system_prompt: &str,
         messages: &[ChatMessage],
-        temperature: f32,
+        options: &ChatOptions,
     ) -> Result<ChatResult> {
-        let url = format!("{}/api/chat", self.base_url);
+        self.send_chat(system_prompt, messages, options, None).await
+    }
+
+    /// Generate a response constrained to valid JSON
+    pub async fn chat_json(
+        &self,
+        system_prompt: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> Result<ChatResult> {
+        self.send_chat(system_prompt, messages, options, Some("json"))
+            .await
+    }
+
+    /// Generate a response with streaming, calling `on_token` for each chunk
+    pub async fn chat_stream(
+        &self,
+        system_prompt: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        on_token: &(dyn Fn(&str) + Send + Sync),
+    ) -> Result<ChatResult> {
+        let request = self.build_request(system_prompt, messages, options, None, true);
+        let start = Instant::now();
+        let mut response = self.post_chat(&request).await?;
+
+        // The body is newline-delimited JSON; a chunk may end mid-line
+        let mut buffer = Vec::new();
+        let mut content = String::new();
+        let mut last = None;
+        let mut handle_line = |line: &[u8]| -> Result<()> {
+            if line.iter().all(u8::is_ascii_whitespace) {
+                return Ok(());
+            }
 
+            let chunk: ChatStreamChunk = serde_json::from_slice(line).map_err(|e| {
+                DialogGenError::GenerationFailed(format!("Bad stream chunk: {}", e))
+            })?;
+            if let Some(message) = &chunk.message
+                && !message.content.is_empty()
+            {
+                on_token(&message.content);
+                content.push_str(&message.content);
+            }
+            if chunk.done {
+                last = Some(chunk);
+            }
+            Ok(())
+        };
+
+        while let Some(bytes) = response.chunk().await? {
+            buffer.extend_from_slice(&bytes);
+            while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=pos).collect();
+                handle_line(&line)?;
+            }
+        }
+        // The final chunk need not end with a newline
+        handle_line(&buffer)?;
+
+        let last = last.ok_or_else(|| {
+            DialogGenError::GenerationFailed("Stream ended before completion".to_string())
+        })?;
+
+        Ok(ChatResult {
+            content,
+            stats: ChatStats {
+                model: self.model.clone(),
+                prompt_tokens: last.prompt_eval_count.unwrap_or(0),
+                completion_tokens: last.eval_count.unwrap_or(0),
+                eval_duration_ns: last.eval_duration.unwrap_or(0),
+                wall_time: start.elapsed(),
+                retries: 0,
+            },
+        })
+    }
+
+    fn build_request(
+        &self,
+        system_prompt: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        format: Option<&'static str>,
+        stream: bool,
+    ) -> ChatRequest {
         let mut all_messages = vec![ChatMessage {
             role: "system".to_string(),
             content: system_prompt.to_string(),
         }];
         all_messages.extend(messages.iter().cloned());
 
-        let request = ChatRequest {
+        ChatRequest {
             model: self.model.clone(),
             messages: all_messages,
-            stream: false,
-            options: ChatOptions { temperature },
-        };
+            stream,
+            options: options.clone(),
+            format,
+        }
+    }
 
-        let start = Instant::now();
+    /// POST a chat request, turning non-2xx responses into errors
+    async fn post_chat(&self, request: &ChatRequest) -> Result<reqwest::Response> {
+        let url = format!("{}/api/chat", self.base_url);
 
         let response = self
             .client
             .post(&url)
-            .json(&request)
+            .json(request)
             .send()
             .await
             .map_err(|e| DialogGenError::OllamaUnavailable(e.to_string()))?;
 
-        let wall_time = start.elapsed();
-
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
@@ -130,13 +307,30 @@ impl OllamaClient {
             )));
         }
 
+        Ok(response)
+    }
+
+    async fn send_chat(
+        &self,
+        system_prompt: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        format: Option<&'static str>,
+    ) -> Result<ChatResult> {
+        let request = self.build_request(system_prompt, messages, options, format, false);
+        let start = Instant::now();
+        let response = self.post_chat(&request).await?;
+        let wall_time = start.elapsed();
+
         let chat_response: ChatResponse = response.json().await?;
 
         let stats = ChatStats {
+            model: self.model.clone(),
             prompt_tokens: chat_response.prompt_eval_count.unwrap_or(0),
             completion_tokens: chat_response.eval_count.unwrap_or(0),
             eval_duration_ns: chat_response.eval_duration.unwrap_or(0),
             wall_time,
+            retries: 0,
         };
 
         Ok(ChatResult {
@@ -145,3 +339,39 @@ impl OllamaClient {
         })
     }
 }
+
+/// The JSON object or array in a model reply, without any prose or code
+/// fences around it. Replies with neither are returned as they are.
+pub(crate) fn extract_json(reply: &str) -> &str {
+    let Some(start) = reply.find(['{', '[']) else {
+        return reply;
+    };
+    let close = if reply[start..].starts_with('{') {
+        '}'
+    } else {
+        ']'
+    };
+    match reply.rfind(close) {
+        Some(end) if start < end => &reply[start..=end],
+        _ => reply,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_json_strips_prose_and_fences() {
+        assert_eq!(
+            extract_json("Sure!\n```json\n{\"a\": {\"b\": 1}}\n```\nHope that helps."),
+            "{\"a\": {\"b\": 1}}"
+        );
+        assert_eq!(
+            extract_json("Edits: [{\"line\": 1}] done"),
+            "[{\"line\": 1}]"
+        );
+        assert_eq!(extract_json("no json here"), "no json here");
+        assert_eq!(extract_json("} backwards {"), "} backwards {");
+    }
+}
@@ -1,21 +1,43 @@
+use crate::analysis::{self, DialogAnalysis};
+use crate::backend::ChatBackend;
 use crate::config::{DialogConfig, Speaker};
+use crate::editor::EditPass;
 use crate::error::Result;
-use crate::ollama::{ChatMessage, OllamaClient};
+use crate::events::{GenerationEvent, GenerationObserver};
+use crate::ollama::{ChatMessage, ChatOptions, ChatResult, ChatStats, OllamaClient};
+use std::sync::Arc;
 use std::time::Duration;
 
-pub struct DialogOrchestrator {
-    ollama: OllamaClient,
+/// System prompt for every turn. `{name}`, `{other_name}`, `{background}`,
+/// `{personality}`, `{speaking_style}`, `{scene}`, `{setting}` and `{notes}`
+/// are filled in from the speakers and directions.
+pub const SYSTEM_PROMPT_TEMPLATE: &str = r#"You are {name} talking to {other_name}.
+
+{name}: {background} {personality} {speaking_style}
+
+Scene: {scene}. {setting}
+{notes}
+
+RESPOND WITH EXACTLY ONE SHORT SENTENCE. Either a statement OR a question, never both. No followup. Just react to what {other_name} said."#;
+
+pub struct DialogOrchestrator<B = OllamaClient> {
+    backend: B,
     config: DialogConfig,
+    system_prompt_template: String,
+    max_retries: u32,
+    observer: Option<Arc<dyn GenerationObserver>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct DialogExchange {
     pub speaker: String,
     pub content: String,
+    /// Stats for the LLM call that produced this line (None for seed lines)
+    pub stats: Option<ChatStats>,
 }
 
 /// Metadata about the generation run
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GenerationMetadata {
     pub model: String,
     pub turns: usize,
@@ -24,6 +46,33 @@ pub struct GenerationMetadata {
     pub total_completion_tokens: u64,
     pub total_wall_time: Duration,
     pub avg_tokens_per_second: f64,
+    /// Heuristic quality metrics for the finished dialog
+    pub analysis: Option<DialogAnalysis>,
+    /// Editing passes applied since generation, in order
+    pub edit_passes: Vec<EditPass>,
+}
+
+/// The exact request a turn would send, for inspecting prompts without a model
+#[derive(Debug)]
+pub struct PromptPreview {
+    pub speaker: String,
+    pub system_prompt: String,
+    pub messages: Vec<ChatMessage>,
+}
+
+impl PromptPreview {
+    /// Rough token count (about 4 characters per token plus a few tokens of
+    /// chat-template overhead per message)
+    pub fn estimated_tokens(&self) -> usize {
+        let per_message = 4;
+        let chars: usize = self.system_prompt.chars().count()
+            + self
+                .messages
+                .iter()
+                .map(|m| m.content.chars().count())
+                .sum::<usize>();
+        chars.div_ceil(4) + per_message * (self.messages.len() + 1)
+    }
 }
 
 #[derive(Debug)]
@@ -32,30 +81,122 @@ pub struct GeneratedDialog {
     pub metadata: GenerationMetadata,
 }
 
-impl DialogOrchestrator {
-    pub fn new(ollama: OllamaClient, config: DialogConfig) -> Self {
-        Self { ollama, config }
+impl GeneratedDialog {
+    /// Wrap a dialog loaded from a script file, e.g. to edit it again.
+    /// Its lines carry no stats, so token and timing totals are zero.
+    pub fn from_script(
+        exchanges: Vec<DialogExchange>,
+        model: impl Into<String>,
+        temperature: f32,
+        speakers: &[Speaker],
+    ) -> Self {
+        let metadata = GenerationMetadata {
+            model: model.into(),
+            turns: exchanges.len(),
+            temperature,
+            total_prompt_tokens: 0,
+            total_completion_tokens: 0,
+            total_wall_time: Duration::ZERO,
+            avg_tokens_per_second: 0.0,
+            analysis: Some(analysis::analyze(&exchanges, speakers)),
+            edit_passes: Vec::new(),
+        };
+
+        Self {
+            exchanges,
+            metadata,
+        }
     }
 
-    /// Build system prompt for a speaker
-    fn build_system_prompt(&self, speaker: &Speaker, other_speaker: &Speaker) -> String {
-        format!(
-            r#"You are {name} talking to {other_name}.
+    /// Stats of the generated turns, in order, skipping seed lines
+    pub fn turn_stats(&self) -> impl Iterator<Item = &ChatStats> {
+        self.exchanges.iter().filter_map(|e| e.stats.as_ref())
+    }
 
-{name}: {background} {personality} {speaking_style}
+    /// Wall-time latency at the given percentile (0-100), nearest-rank method
+    pub fn latency_percentile(&self, percentile: f64) -> Option<Duration> {
+        let mut latencies: Vec<Duration> = self.turn_stats().map(|s| s.wall_time).collect();
+        if latencies.is_empty() {
+            return None;
+        }
+        latencies.sort();
 
-Scene: {scene}. {setting}
-{notes}
+        let rank = ((percentile / 100.0) * latencies.len() as f64).ceil() as usize;
+        Some(latencies[rank.clamp(1, latencies.len()) - 1])
+    }
+}
+
+impl<B: ChatBackend> DialogOrchestrator<B> {
+    pub fn new(backend: B, config: DialogConfig) -> Self {
+        Self {
+            backend,
+            config,
+            system_prompt_template: SYSTEM_PROMPT_TEMPLATE.to_string(),
+            max_retries: 0,
+            observer: None,
+        }
+    }
+
+    /// Retry a turn that fails or comes back empty up to `retries` times.
+    /// Off by default; each retry is another model call.
+    pub fn with_max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Use a different system prompt template, e.g. the one recorded in a
+    /// run manifest. Unknown `{placeholders}` are left as they are.
+    pub fn with_system_prompt_template(mut self, template: impl Into<String>) -> Self {
+        self.system_prompt_template = template.into();
+        self
+    }
+
+    pub fn system_prompt_template(&self) -> &str {
+        &self.system_prompt_template
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
 
-RESPOND WITH EXACTLY ONE SHORT SENTENCE. Either a statement OR a question, never both. No followup. Just react to what {other_name} said."#,
-            name = speaker.name,
-            other_name = other_speaker.name,
-            background = speaker.background,
-            personality = speaker.personality,
-            speaking_style = speaker.speaking_style,
-            scene = self.config.directions.scene_name,
-            setting = self.config.directions.setting,
-            notes = self.config.directions.notes,
+    /// Sampling options sent with every turn
+    pub fn chat_options(&self) -> ChatOptions {
+        ChatOptions {
+            temperature: self.config.scene.temperature,
+            seed: self.config.scene.seed,
+        }
+    }
+
+    /// Report progress events (including streamed tokens) to `observer`
+    pub fn with_observer(mut self, observer: Arc<dyn GenerationObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    fn emit(&self, event: GenerationEvent) {
+        if let Some(observer) = &self.observer {
+            observer.on_event(event);
+        }
+    }
+
+    /// Build system prompt for a speaker
+    fn build_system_prompt(&self, speaker: &Speaker, other_speaker: &Speaker) -> String {
+        render_template(
+            &self.system_prompt_template,
+            &[
+                ("name", &speaker.name),
+                ("other_name", &other_speaker.name),
+                ("background", &speaker.background),
+                ("personality", &speaker.personality),
+                ("speaking_style", &speaker.speaking_style),
+                ("scene", &self.config.directions.scene_name),
+                ("setting", &self.config.directions.setting),
+                ("notes", &self.config.directions.notes),
+            ],
         )
     }
 
@@ -103,80 +244,131 @@ RESPOND WITH EXACTLY ONE SHORT SENTENCE. Either a statement OR a question, never
         }
     }
 
-    /// Generate the complete dialog
-    pub async fn generate(&self, verbose: bool) -> Result<GeneratedDialog> {
-        let mut exchanges: Vec<DialogExchange> = self
-            .config
+    /// Seed lines from prompt.txt as exchanges
+    pub fn seed_exchanges(&self) -> Vec<DialogExchange> {
+        self.config
             .initial_lines
             .iter()
             .map(|l| DialogExchange {
                 speaker: l.speaker.clone(),
                 content: l.content.clone(),
+                stats: None,
             })
-            .collect();
+            .collect()
+    }
 
-        // Determine starting speaker (opposite of last in prompt)
+    /// Assemble the requests for each speaker's first turn without calling
+    /// the backend. The second speaker's history ends with a placeholder
+    /// for the first speaker's not-yet-generated reply.
+    pub fn preview_first_turns(&self) -> Vec<PromptPreview> {
+        let mut exchanges = self.seed_exchanges();
         let last_speaker = self.config.initial_lines.last().map(|l| l.speaker.as_str());
+        let mut speaker = self.get_next_speaker(last_speaker);
+        let mut previews = Vec::new();
+
+        for _ in 0..self.config.scene.turns.min(2) {
+            let other_speaker = self.get_other_speaker(speaker);
+            previews.push(PromptPreview {
+                speaker: speaker.name.clone(),
+                system_prompt: self.build_system_prompt(speaker, other_speaker),
+                messages: self.build_conversation_history(speaker, &exchanges),
+            });
 
-        if verbose {
-            eprintln!("Initial dialog:");
-            for line in &self.config.initial_lines {
-                eprintln!("  {}: {}", line.speaker, line.content);
-            }
-            eprintln!("\nGenerating {} turns...\n", self.config.scene.turns);
+            exchanges.push(DialogExchange {
+                speaker: speaker.name.clone(),
+                content: format!("<{}'s generated reply>", speaker.name),
+                stats: None,
+            });
+            speaker = other_speaker;
         }
 
-        let mut current_speaker = self.get_next_speaker(last_speaker);
-
-        // Track aggregate stats
-        let mut total_prompt_tokens: u64 = 0;
-        let mut total_completion_tokens: u64 = 0;
-        let mut total_wall_time = Duration::ZERO;
-        let mut total_eval_ns: u64 = 0;
+        previews
+    }
 
-        for turn in 0..self.config.scene.turns {
-            if verbose {
-                eprint!(
-                    "Turn {}/{}: {} ... ",
-                    turn + 1,
-                    self.config.scene.turns,
-                    current_speaker.name
-                );
-            }
+    /// The scene being generated
+    pub fn config(&self) -> &DialogConfig {
+        &self.config
+    }
 
-            let other_speaker = self.get_other_speaker(current_speaker);
-            let system_prompt = self.build_system_prompt(current_speaker, other_speaker);
-            let messages = self.build_conversation_history(current_speaker, &exchanges);
+    /// Generate the complete dialog
+    pub async fn generate(&self) -> Result<GeneratedDialog> {
+        let mut exchanges = self.seed_exchanges();
 
-            let result = self
-                .ollama
-                .chat(&system_prompt, &messages, self.config.scene.temperature)
-                .await?;
+        self.emit(GenerationEvent::Started {
+            initial_lines: self.config.initial_lines.clone(),
+            turns: self.config.scene.turns,
+        });
 
-            // Accumulate stats
-            total_prompt_tokens += result.stats.prompt_tokens;
-            total_completion_tokens += result.stats.completion_tokens;
-            total_wall_time += result.stats.wall_time;
-            total_eval_ns += result.stats.eval_duration_ns;
+        for _ in 0..self.config.scene.turns {
+            let line = self.generate_line(&exchanges, &[]).await?;
+            exchanges.push(line);
+        }
 
-            // Clean up response (remove any accidental name prefix)
-            let cleaned_response = self.clean_response(&result.content, &current_speaker.name);
+        let dialog = self.finish(exchanges);
+        self.emit(GenerationEvent::Finished {
+            metadata: dialog.metadata.clone(),
+        });
+        Ok(dialog)
+    }
 
-            if verbose {
-                eprintln!("{}", cleaned_response);
-            }
+    /// Generate the next line after `exchanges` without adding it, so a
+    /// caller can review, regenerate or edit each turn. The speaker is the
+    /// one who didn't speak last; `director_notes` are appended to their
+    /// system prompt.
+    pub async fn generate_line(
+        &self,
+        exchanges: &[DialogExchange],
+        director_notes: &[String],
+    ) -> Result<DialogExchange> {
+        let turn = exchanges.iter().filter(|e| e.stats.is_some()).count() + 1;
+        let last_speaker = exchanges.last().map(|e| e.speaker.as_str());
+        let current_speaker = self.get_next_speaker(last_speaker);
+
+        self.emit(GenerationEvent::TurnStarted {
+            turn,
+            total: self.config.scene.turns,
+            speaker: current_speaker.name.clone(),
+        });
+
+        let other_speaker = self.get_other_speaker(current_speaker);
+        let mut system_prompt = self.build_system_prompt(current_speaker, other_speaker);
+        for note in director_notes {
+            system_prompt.push_str(&format!("\n\nDirector's note: {}", note));
+        }
+        let messages = self.build_conversation_history(current_speaker, exchanges);
+
+        let (cleaned_response, stats) = self
+            .generate_turn(&system_prompt, &messages, &current_speaker.name, turn)
+            .await?;
+
+        self.emit(GenerationEvent::TurnCompleted {
+            turn,
+            speaker: current_speaker.name.clone(),
+            content: cleaned_response.clone(),
+            stats: stats.clone(),
+        });
+
+        Ok(DialogExchange {
+            speaker: current_speaker.name.clone(),
+            content: cleaned_response,
+            stats: Some(stats),
+        })
+    }
 
-            exchanges.push(DialogExchange {
-                speaker: current_speaker.name.clone(),
-                content: cleaned_response,
-            });
+    /// Wrap finished exchanges with aggregate metadata
+    pub fn finish(&self, exchanges: Vec<DialogExchange>) -> GeneratedDialog {
+        let mut turns = 0;
+        let mut total_prompt_tokens: u64 = 0;
+        let mut total_completion_tokens: u64 = 0;
+        let mut total_wall_time = Duration::ZERO;
+        let mut total_eval_ns: u64 = 0;
 
-            // Alternate speakers
-            current_speaker = if current_speaker.name == self.config.speaker1.name {
-                &self.config.speaker2
-            } else {
-                &self.config.speaker1
-            };
+        for stats in exchanges.iter().filter_map(|e| e.stats.as_ref()) {
+            turns += 1;
+            total_prompt_tokens += stats.prompt_tokens;
+            total_completion_tokens += stats.completion_tokens;
+            total_wall_time += stats.wall_time;
+            total_eval_ns += stats.eval_duration_ns;
         }
 
         let avg_tokens_per_second = if total_eval_ns > 0 {
@@ -186,19 +378,90 @@ RESPOND WITH EXACTLY ONE SHORT SENTENCE. Either a statement OR a question, never
         };
 
         let metadata = GenerationMetadata {
-            model: self.ollama.model().to_string(),
-            turns: self.config.scene.turns,
+            model: self.backend.model().to_string(),
+            turns,
             temperature: self.config.scene.temperature,
             total_prompt_tokens,
             total_completion_tokens,
             total_wall_time,
             avg_tokens_per_second,
+            analysis: Some(analysis::analyze(
+                &exchanges,
+                &[self.config.speaker1.clone(), self.config.speaker2.clone()],
+            )),
+            edit_passes: Vec::new(),
         };
 
-        Ok(GeneratedDialog {
+        GeneratedDialog {
             exchanges,
             metadata,
-        })
+        }
+    }
+
+    /// Run a single turn, retrying failed calls and empty responses.
+    /// Token counts and wall time include attempts that came back empty.
+    /// Replies are streamed only when someone is observing.
+    async fn generate_turn(
+        &self,
+        system_prompt: &str,
+        messages: &[ChatMessage],
+        speaker_name: &str,
+        turn: usize,
+    ) -> Result<(String, ChatStats)> {
+        let options = self.chat_options();
+        let mut retries = 0;
+        let mut wasted = ChatStats::default();
+
+        loop {
+            let attempt = match &self.observer {
+                Some(observer) => {
+                    let on_token = |text: &str| {
+                        observer.on_event(GenerationEvent::Token {
+                            turn,
+                            text: text.to_string(),
+                        })
+                    };
+                    self.backend
+                        .chat_stream(system_prompt, messages, &options, &on_token)
+                        .await
+                }
+                None => self.backend.chat(system_prompt, messages, &options).await,
+            };
+
+            let error = match attempt {
+                Ok(ChatResult { content, stats }) => {
+                    // Clean up response (remove any accidental name prefix)
+                    let cleaned = self.clean_response(&content, speaker_name);
+                    if !cleaned.is_empty() || retries == self.max_retries {
+                        let stats = ChatStats {
+                            prompt_tokens: stats.prompt_tokens + wasted.prompt_tokens,
+                            completion_tokens: stats.completion_tokens + wasted.completion_tokens,
+                            eval_duration_ns: stats.eval_duration_ns + wasted.eval_duration_ns,
+                            wall_time: stats.wall_time + wasted.wall_time,
+                            retries,
+                            ..stats
+                        };
+                        return Ok((cleaned, stats));
+                    }
+
+                    wasted.prompt_tokens += stats.prompt_tokens;
+                    wasted.completion_tokens += stats.completion_tokens;
+                    wasted.eval_duration_ns += stats.eval_duration_ns;
+                    wasted.wall_time += stats.wall_time;
+                    "empty response".to_string()
+                }
+                Err(e) if retries < self.max_retries => e.to_string(),
+                Err(e) => return Err(e),
+            };
+
+            retries += 1;
+            self.emit(GenerationEvent::Retry {
+                turn,
+                retry: retries,
+                max_retries: self.max_retries,
+                error,
+            });
+        }
     }
 
     /// Clean up LLM response - remove name prefix, truncate if needed
@@ -240,3 +503,100 @@ RESPOND WITH EXACTLY ONE SHORT SENTENCE. Either a statement OR a question, never
         result
     }
 }
+
+/// Replace `{key}` placeholders in one pass, so braces inside the values
+/// are never expanded
+fn render_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.find('}').and_then(|end| {
+            values
+                .iter()
+                .find(|(key, _)| *key == &after[..end])
+                .map(|(_, value)| (end, value))
+        });
+        match value {
+            Some((end, value)) => {
+                out.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_template_fills_known_placeholders() {
+        let values = [("speaker", "Maya"), ("other", "Dev")];
+
+        assert_eq!(
+            render_template("{speaker} talks to {other}.", &values),
+            "Maya talks to Dev."
+        );
+        assert_eq!(
+            render_template("{speaker} says {\"json\": {unknown}} {", &values),
+            "Maya says {\"json\": {unknown}} {"
+        );
+    }
+
+    #[test]
+    fn render_template_does_not_expand_values() {
+        let values = [("speaker", "{other}"), ("other", "Dev")];
+        assert_eq!(render_template("{speaker}/{other}", &values), "{other}/Dev");
+    }
+
+    #[tokio::test]
+    async fn retries_are_counted_from_one_up_to_max_retries() {
+        use crate::backend::CannedBackend;
+        use crate::error::DialogGenError;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = AtomicUsize::new(0);
+        let backend = CannedBackend(|_: &str, _: &[ChatMessage]| {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(DialogGenError::GenerationFailed("timed out".to_string())),
+                1 => Ok("   ".to_string()),
+                _ => Ok("Third time lucky.".to_string()),
+            }
+        });
+        let config = DialogConfig::builder()
+            .speaker1(Speaker::new("Maya"))
+            .speaker2(Speaker::new("Dev"))
+            .turns(1)
+            .build()
+            .unwrap();
+        let (events, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let orchestrator = DialogOrchestrator::new(backend, config)
+            .with_max_retries(2)
+            .with_observer(Arc::new(events));
+
+        let dialog = orchestrator.generate().await.unwrap();
+        assert_eq!(dialog.exchanges[0].content, "Third time lucky.");
+        assert_eq!(dialog.exchanges[0].stats.as_ref().unwrap().retries, 2);
+
+        let mut retries = Vec::new();
+        while let Ok(event) = received.try_recv() {
+            if let GenerationEvent::Retry {
+                retry, max_retries, ..
+            } = event
+            {
+                retries.push((retry, max_retries));
+            }
+        }
+        assert_eq!(retries, [(1, 2), (2, 2)]);
+    }
+}
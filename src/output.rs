@@ -1,15 +1,207 @@
-use crate::error::Result;
+use crate::config::DialogConfig;
+use crate::error::{DialogGenError, Result};
+use crate::fidelity::FidelityReport;
+use crate::formats::{ExportFormat, ExportOptions, fountain, ink, ssml, subtitles, yarn};
+use crate::manifest::RunManifest;
 use crate::orchestrator::GeneratedDialog;
-use std::fs;
-use std::path::PathBuf;
+use std::fmt::{self, Write};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Numbers tried when claiming an output file before giving up
+const MAX_RESERVE_ATTEMPTS: usize = 100;
+
+/// Directory under the output directory that holds per-run directories
+pub const RUNS_DIR: &str = "runs";
+
+/// Symlink in the runs directory that points at the newest run
+const LATEST_LINK: &str = "latest";
+
+/// How a run's files are arranged in the output directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum OutputLayout {
+    /// generated-dialogN.txt and friends directly in the output directory
+    #[default]
+    Flat,
+    /// A new runs/<timestamp>_<model>/ directory for every run, with
+    /// runs/latest pointing at the newest
+    Runs,
+}
+
+impl OutputLayout {
+    /// Every output layout, in the order `--help` lists them
+    pub const ALL: &[OutputLayout] = &[OutputLayout::Flat, OutputLayout::Runs];
+
+    /// Name used on the command line and in scene files
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputLayout::Flat => "flat",
+            OutputLayout::Runs => "runs",
+        }
+    }
+}
+
+impl fmt::Display for OutputLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for OutputLayout {
+    type Err = DialogGenError;
+
+    /// Parse a name, ignoring case
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|v| v.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| DialogGenError::InvalidConfig(format!("unknown output layout '{}'", s)))
+    }
+}
 
 pub struct OutputWriter {
     output_dir: PathBuf,
+    /// Why `runs/latest` couldn't be pointed at this run, if it couldn't
+    latest_link_error: Option<String>,
+}
+
+/// File naming for one edit of a source dialog. Edits of
+/// `generated-dialog3.txt` are `edited-podcast3.txt`, `edited-metadata3.txt`
+/// and so on; other sources use their file stem (`edited-podcast-script.txt`).
+/// Later attempts kept alongside the first get `-2`, `-3`, ... appended.
+#[derive(Debug, Clone)]
+pub struct EditVersion {
+    source: PathBuf,
+    label: String,
+    attempt: usize,
+    /// The edited file was claimed empty and must be released on failure
+    reserved: bool,
+}
+
+impl EditVersion {
+    /// The dialog file that was edited
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    /// 1 for the first edit of a source, 2 for the next kept attempt, ...
+    pub fn attempt(&self) -> usize {
+        self.attempt
+    }
+
+    fn file_name(&self, kind: &str, extra: &str) -> String {
+        format!("edited-{}{}{}.txt", kind, self.label, extra)
+    }
+}
+
+/// Model and temperature recorded for a generated dialog
+#[derive(Debug, Clone, Default)]
+pub struct SourceMetadata {
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+}
+
+impl SourceMetadata {
+    /// Read output-metadataN.txt next to a generated-dialogN.txt, if there
+    /// is one
+    pub fn read(dialog_path: &Path) -> Self {
+        let mut source = Self::default();
+        let Some(num) = dialog_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("generated-dialog"))
+            .and_then(|rest| rest.strip_suffix(".txt"))
+        else {
+            return source;
+        };
+        let path = dialog_path.with_file_name(format!("output-metadata{}.txt", num));
+        let Ok(content) = fs::read_to_string(path) else {
+            return source;
+        };
+
+        for line in content.lines() {
+            if let Some(model) = line.strip_prefix("Model: ") {
+                source.model.get_or_insert_with(|| model.trim().to_string());
+            } else if let Some(temperature) = line.strip_prefix("Temperature: ") {
+                source.temperature = source.temperature.or(temperature.trim().parse().ok());
+            }
+        }
+        source
+    }
 }
 
 impl OutputWriter {
     pub fn new(output_dir: PathBuf) -> Self {
-        Self { output_dir }
+        Self {
+            output_dir,
+            latest_link_error: None,
+        }
+    }
+
+    /// A writer for `output_dir` arranged as `layout`. With the runs layout
+    /// this creates the run's directory, so call it once the run is done.
+    pub fn with_layout(output_dir: PathBuf, layout: OutputLayout, model: &str) -> Result<Self> {
+        match layout {
+            OutputLayout::Flat => Ok(Self::new(output_dir)),
+            OutputLayout::Runs => Self::new_run(&output_dir.join(RUNS_DIR), model),
+        }
+    }
+
+    /// Create a directory for a new run in `runs_dir`, named after the
+    /// current time and model (e.g. `2026-10-17T10-22-05_llama3.2`), and
+    /// point `runs_dir/latest` at it. The link is a convenience: if it can't
+    /// be made (Windows needs a privilege for directory links) the run still
+    /// goes ahead and [`latest_link_error`](Self::latest_link_error) says why.
+    pub fn new_run(runs_dir: &Path, model: &str) -> Result<Self> {
+        fs::create_dir_all(runs_dir)?;
+
+        let timestamp = chrono::Local::now().format("%Y-%m-%dT%H-%M-%S");
+        let base = format!("{}_{}", timestamp, run_model_label(model));
+        let mut name = base.clone();
+        for attempt in 2..=MAX_RESERVE_ATTEMPTS + 1 {
+            match fs::create_dir(runs_dir.join(&name)) {
+                Ok(()) => {
+                    let latest_link_error = update_latest_link(runs_dir, &name).err().map(|e| {
+                        format!(
+                            "could not point {} at {}: {}",
+                            runs_dir.join(LATEST_LINK).display(),
+                            name,
+                            e
+                        )
+                    });
+                    return Ok(Self {
+                        output_dir: runs_dir.join(name),
+                        latest_link_error,
+                    });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    name = format!("{}-{}", base, attempt);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("no free run directory name in {}", runs_dir.display()),
+        )
+        .into())
+    }
+
+    pub fn output_dir(&self) -> &Path {
+        &self.output_dir
+    }
+
+    /// Why `runs/latest` couldn't be updated for this run, when the writer
+    /// came from [`new_run`](Self::new_run) and the link failed
+    pub fn latest_link_error(&self) -> Option<&str> {
+        self.latest_link_error.as_deref()
     }
 
     /// Find next available output file number
@@ -35,67 +227,260 @@ impl OutputWriter {
         Ok(max_num + 1)
     }
 
+    /// Claim the next free generated-dialogN.txt by creating it empty, so
+    /// runs writing to the same directory at once each get their own number
+    fn reserve_file_number(&self) -> Result<usize> {
+        let first = self.next_file_number()?;
+        for num in first..first + MAX_RESERVE_ATTEMPTS {
+            let path = self.output_dir.join(format!("generated-dialog{}.txt", num));
+            if reserve(&path)? {
+                return Ok(num);
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("no free output number in {}", self.output_dir.display()),
+        )
+        .into())
+    }
+
     /// Write generated dialog and metadata to files
     pub fn write(&self, dialog: &GeneratedDialog) -> Result<PathBuf> {
         // Ensure output directory exists
         fs::create_dir_all(&self.output_dir)?;
 
-        let num = self.next_file_number()?;
+        let num = self.reserve_file_number()?;
         let dialog_path = self.output_dir.join(format!("generated-dialog{}.txt", num));
         let metadata_path = self.output_dir.join(format!("output-metadata{}.txt", num));
 
-        // Write dialog
         let mut content = String::new();
         for exchange in &dialog.exchanges {
             content.push_str(&format!("{}: {}\n\n", exchange.speaker, exchange.content));
         }
-        fs::write(&dialog_path, content.trim_end())?;
 
-        // Write metadata
-        let meta = &dialog.metadata;
-        let metadata_content = format!(
-            "Model: {}\n\
-             Turns: {}\n\
-             Temperature: {:.2}\n\
-             \n\
-             Prompt tokens: {}\n\
-             Completion tokens: {}\n\
-             Total tokens: {}\n\
-             \n\
-             Wall time: {:.2}s\n\
-             Tokens/second: {:.1}\n",
-            meta.model,
-            meta.turns,
-            meta.temperature,
-            meta.total_prompt_tokens,
-            meta.total_completion_tokens,
-            meta.total_prompt_tokens + meta.total_completion_tokens,
-            meta.total_wall_time.as_secs_f64(),
-            meta.avg_tokens_per_second,
-        );
-        fs::write(&metadata_path, metadata_content)?;
+        // Metadata goes first, so a dialog with content always has its
+        // metadata next to it. On failure the reserved number is released.
+        let written = write_atomic(&metadata_path, Self::format_metadata(dialog))
+            .and_then(|_| write_atomic(&dialog_path, content.trim_end()));
+        if written.is_err() {
+            let _ = fs::remove_file(&metadata_path);
+            let _ = fs::remove_file(&dialog_path);
+        }
+        written?;
 
         Ok(dialog_path)
     }
 
-    /// Write edited podcast dialog to file
-    pub fn write_edited(&self, dialog: &GeneratedDialog) -> Result<PathBuf> {
+    /// Write the run manifest for an already written dialog file
+    /// (generated-dialog3.txt gets run-manifest3.json)
+    pub fn write_manifest(&self, dialog_path: &Path, manifest: &RunManifest) -> Result<PathBuf> {
+        fs::create_dir_all(&self.output_dir)?;
+
+        let stem = dialog_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let name = match stem.strip_prefix("generated-dialog") {
+            Some(num) => format!("run-manifest{}.json", num),
+            None => format!("{}-manifest.json", stem),
+        };
+        let path = self.output_dir.join(name);
+        write_atomic(&path, manifest.to_json()?)?;
+        Ok(path)
+    }
+
+    /// Name the edit of `source`. With `keep_attempts` earlier edits of the
+    /// same source are left alone and the next free attempt number is
+    /// claimed; otherwise the first attempt is overwritten. Claim the version
+    /// once the edit has succeeded and write it straight away.
+    pub fn edit_version(&self, source: &Path, keep_attempts: bool) -> Result<EditVersion> {
+        let stem = source
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let base = match stem.strip_prefix("generated-dialog") {
+            Some(num) if num.parse::<usize>().is_ok() => num.to_string(),
+            _ => format!("-{}", stem),
+        };
+
+        let mut version = EditVersion {
+            source: source.to_path_buf(),
+            label: base.clone(),
+            attempt: 1,
+            reserved: keep_attempts,
+        };
+        if !keep_attempts {
+            return Ok(version);
+        }
+
+        fs::create_dir_all(&self.output_dir)?;
+        for attempt in 2..=MAX_RESERVE_ATTEMPTS + 1 {
+            if reserve(&self.output_dir.join(version.file_name("podcast", "")))? {
+                return Ok(version);
+            }
+            version.attempt = attempt;
+            version.label = format!("{}-{}", base, attempt);
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "no free edit attempt for {} in {}",
+                source.display(),
+                self.output_dir.display()
+            ),
+        )
+        .into())
+    }
+
+    /// Write edited podcast dialog and its metadata, which records the source
+    pub fn write_edited(&self, version: &EditVersion, dialog: &GeneratedDialog) -> Result<PathBuf> {
         // Ensure output directory exists
         fs::create_dir_all(&self.output_dir)?;
 
-        let edited_path = self.output_dir.join("edited-podcast.txt");
-        let metadata_path = self.output_dir.join("edited-metadata.txt");
+        let edited_path = self.output_dir.join(version.file_name("podcast", ""));
+        let metadata_path = self.output_dir.join(version.file_name("metadata", ""));
 
         // Write edited dialog
         let mut content = String::new();
         for exchange in &dialog.exchanges {
             content.push_str(&format!("{}: {}\n\n", exchange.speaker, exchange.content));
         }
-        fs::write(&edited_path, content.trim_end())?;
 
-        // Write metadata
+        // Write metadata, led by where the dialog came from
+        let source = if version.source.parent() == Some(self.output_dir.as_path()) {
+            version
+                .source
+                .file_name()
+                .map(Path::new)
+                .unwrap_or(&version.source)
+        } else {
+            &version.source
+        };
+        let mut metadata = format!("Source: {}\n", source.display());
+        if version.attempt > 1 {
+            let _ = writeln!(metadata, "Attempt: {}", version.attempt);
+        }
+        metadata.push('\n');
+        metadata.push_str(&Self::format_metadata(dialog));
+
+        // As in `write`, metadata goes first and a claimed attempt number is
+        // released on failure
+        let written = write_atomic(&metadata_path, metadata)
+            .and_then(|_| write_atomic(&edited_path, content.trim_end()));
+        if written.is_err() && version.reserved {
+            let _ = fs::remove_file(&metadata_path);
+            let _ = fs::remove_file(&edited_path);
+        }
+        written?;
+
+        Ok(edited_path)
+    }
+
+    /// Write one pass of a multi-pass edit (edited-podcast3-pass1-tighten.txt)
+    pub fn write_edit_pass(
+        &self,
+        version: &EditVersion,
+        index: usize,
+        name: &str,
+        dialog: &GeneratedDialog,
+    ) -> Result<PathBuf> {
+        fs::create_dir_all(&self.output_dir)?;
+
+        let path = self
+            .output_dir
+            .join(version.file_name("podcast", &format!("-pass{}-{}", index, name)));
+        let mut content = String::new();
+        for exchange in &dialog.exchanges {
+            content.push_str(&format!("{}: {}\n\n", exchange.speaker, exchange.content));
+        }
+        write_atomic(&path, content.trim_end())?;
+        Ok(path)
+    }
+
+    /// Write the line-by-line fidelity report for an edit
+    pub fn write_edited_diff(
+        &self,
+        version: &EditVersion,
+        report: &FidelityReport,
+    ) -> Result<PathBuf> {
+        fs::create_dir_all(&self.output_dir)?;
+
+        let path = self.output_dir.join(version.file_name("diff", ""));
+        write_atomic(&path, report.format())?;
+        Ok(path)
+    }
+
+    /// Write an export next to an already written dialog file, named after it
+    /// (e.g. generated-dialog3.ssml). Returns the file or directory written.
+    pub fn write_export(
+        &self,
+        dialog_path: &Path,
+        format: ExportFormat,
+        dialog: &GeneratedDialog,
+        config: &DialogConfig,
+        options: &ExportOptions,
+    ) -> Result<PathBuf> {
+        let speakers = [config.speaker1.clone(), config.speaker2.clone()];
+        let stem = dialog_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "dialog".to_string());
+
+        match format {
+            ExportFormat::Ssml => {
+                let path = self.output_dir.join(format!("{}.ssml", stem));
+                write_atomic(&path, ssml::render_combined(dialog, &speakers))?;
+                Ok(path)
+            }
+            ExportFormat::SsmlLines => {
+                let dir = self.output_dir.join(format!("{}-ssml", stem));
+                fs::create_dir_all(&dir)?;
+                for (i, doc) in ssml::render_lines(dialog, &speakers).iter().enumerate() {
+                    write_atomic(&dir.join(format!("{:03}.ssml", i + 1)), doc)?;
+                }
+                Ok(dir)
+            }
+            ExportFormat::Srt | ExportFormat::Vtt => {
+                let mut durations = subtitles::estimate_durations(dialog, &speakers);
+                if let Some(manifest_path) = &options.tts_manifest {
+                    let manifest = subtitles::load_tts_manifest(manifest_path)?;
+                    subtitles::apply_manifest(&mut durations, &manifest);
+                }
+                let cues = subtitles::build_cues(dialog, &durations, options.subtitle_gap);
+
+                let (extension, content) = if format == ExportFormat::Srt {
+                    ("srt", subtitles::render_srt(&cues))
+                } else {
+                    ("vtt", subtitles::render_vtt(&cues))
+                };
+                let path = self.output_dir.join(format!("{}.{}", stem, extension));
+                write_atomic(&path, content)?;
+                Ok(path)
+            }
+            ExportFormat::Fountain => {
+                let path = self.output_dir.join(format!("{}.fountain", stem));
+                write_atomic(&path, fountain::render(dialog, &config.directions))?;
+                Ok(path)
+            }
+            ExportFormat::Ink => {
+                let path = self.output_dir.join(format!("{}.ink", stem));
+                write_atomic(&path, ink::render(dialog, &config.directions))?;
+                Ok(path)
+            }
+            ExportFormat::Yarn => {
+                let path = self.output_dir.join(format!("{}.yarn", stem));
+                write_atomic(&path, yarn::render(dialog, &config.directions))?;
+                Ok(path)
+            }
+        }
+    }
+
+    /// Format run totals, the per-turn table and latency percentiles
+    fn format_metadata(dialog: &GeneratedDialog) -> String {
         let meta = &dialog.metadata;
-        let metadata_content = format!(
+        let mut content = format!(
             "Model: {}\n\
              Turns: {}\n\
              Temperature: {:.2}\n\
@@ -115,8 +500,326 @@ impl OutputWriter {
             meta.total_wall_time.as_secs_f64(),
             meta.avg_tokens_per_second,
         );
-        fs::write(&metadata_path, metadata_content)?;
 
-        Ok(edited_path)
+        if let (Some(p50), Some(p95)) = (
+            dialog.latency_percentile(50.0),
+            dialog.latency_percentile(95.0),
+        ) {
+            let _ = write!(
+                content,
+                "Latency p50: {:.2}s\n\
+                 Latency p95: {:.2}s\n",
+                p50.as_secs_f64(),
+                p95.as_secs_f64(),
+            );
+        }
+
+        if let Some(analysis) = &meta.analysis {
+            content.push_str("\nQuality:\n");
+            content.push_str(&analysis.format());
+        }
+
+        if !meta.edit_passes.is_empty() {
+            content.push_str("\nEdit passes:\n");
+            let _ = writeln!(
+                content,
+                "{:>4}  {:<20} {:<7} {:>5} {:>7} {:>10} {:>8}  Model",
+                "Pass", "Name", "Mode", "Temp", "Prompt", "Completion", "Wall"
+            );
+            for (i, pass) in meta.edit_passes.iter().enumerate() {
+                let _ = writeln!(
+                    content,
+                    "{:>4}  {:<20} {:<7} {:>5.2} {:>7} {:>10} {:>7.2}s  {}",
+                    i + 1,
+                    pass.name,
+                    pass.mode.name(),
+                    pass.temperature,
+                    pass.stats.prompt_tokens,
+                    pass.stats.completion_tokens,
+                    pass.stats.wall_time.as_secs_f64(),
+                    pass.stats.model,
+                );
+            }
+            for (i, pass) in meta.edit_passes.iter().enumerate() {
+                for dropped in &pass.dropped_edits {
+                    let _ = writeln!(content, "Pass {} dropped edit: {}", i + 1, dropped);
+                }
+            }
+        }
+
+        let turns: Vec<_> = dialog
+            .exchanges
+            .iter()
+            .filter_map(|e| e.stats.as_ref().map(|stats| (&e.speaker, stats)))
+            .collect();
+
+        if !turns.is_empty() {
+            content.push_str("\nPer-turn stats:\n");
+            let _ = writeln!(
+                content,
+                "{:>4}  {:<12} {:>7} {:>10} {:>8} {:>8} {:>6} {:>7}  Model",
+                "Turn", "Speaker", "Prompt", "Completion", "Eval", "Wall", "Tok/s", "Retries"
+            );
+            for (i, (speaker, stats)) in turns.iter().enumerate() {
+                let _ = writeln!(
+                    content,
+                    "{:>4}  {:<12} {:>7} {:>10} {:>7.2}s {:>7.2}s {:>6.1} {:>7}  {}",
+                    i + 1,
+                    speaker,
+                    stats.prompt_tokens,
+                    stats.completion_tokens,
+                    stats.eval_duration_ns as f64 / 1_000_000_000.0,
+                    stats.wall_time.as_secs_f64(),
+                    stats.tokens_per_second(),
+                    stats.retries,
+                    stats.model,
+                );
+            }
+        }
+
+        content
+    }
+}
+
+/// Create `path` only if it doesn't exist yet. Returns false when it does,
+/// e.g. because another run claimed it first.
+fn reserve(path: &Path) -> Result<bool> {
+    match OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Write to a temporary file next to `path`, then rename it into place so
+/// an interrupted write never leaves a partial file behind
+fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        file_name,
+        process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents.as_ref())?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    Ok(result?)
+}
+
+/// Model name as used in a run directory name: `llama3.2:latest` becomes
+/// `llama3.2` and `mistral:7b` becomes `mistral-7b`
+pub(crate) fn run_model_label(model: &str) -> String {
+    model
+        .trim_end_matches(":latest")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Point `runs_dir/latest` at `run_name`, replacing the old link in one step
+fn update_latest_link(runs_dir: &Path, run_name: &str) -> Result<()> {
+    static LINK_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let temp_link = runs_dir.join(format!(
+        ".{}.{}-{}.tmp",
+        LATEST_LINK,
+        process::id(),
+        LINK_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(run_name, &temp_link)?;
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_dir(run_name, &temp_link)?;
+
+    let link = runs_dir.join(LATEST_LINK);
+    let mut result = fs::rename(&temp_link, &link);
+    // Windows won't rename over an existing directory link; drop the old
+    // one and try again
+    if result.is_err() && fs::symlink_metadata(&link).is_ok_and(|m| m.file_type().is_symlink()) {
+        result = remove_link(&link).and_then(|_| fs::rename(&temp_link, &link));
+    }
+    if let Err(e) = result {
+        let _ = remove_link(&temp_link);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Remove a symlink. Directory links on Windows are removed as directories.
+fn remove_link(path: &Path) -> io::Result<()> {
+    fs::remove_file(path).or_else(|_| fs::remove_dir(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orchestrator::DialogExchange;
+
+    /// An empty directory under the system temp dir, removed on drop
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("dialog-gen-test-{}-{}", process::id(), name));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn dialog() -> GeneratedDialog {
+        let exchanges = vec![DialogExchange {
+            speaker: "Maya".to_string(),
+            content: "Hello.".to_string(),
+            stats: None,
+        }];
+        GeneratedDialog::from_script(exchanges, "test", 0.7, &[])
+    }
+
+    #[test]
+    fn write_takes_the_next_free_number() {
+        let dir = TempDir::new("write-numbers");
+        fs::write(dir.0.join("generated-dialog7.txt"), "").unwrap();
+        fs::write(dir.0.join("generated-dialog-notes.txt"), "").unwrap();
+        let writer = OutputWriter::new(dir.0.clone());
+
+        let first = writer.write(&dialog()).unwrap();
+        let second = writer.write(&dialog()).unwrap();
+
+        assert_eq!(first, dir.0.join("generated-dialog8.txt"));
+        assert_eq!(second, dir.0.join("generated-dialog9.txt"));
+        assert_eq!(fs::read_to_string(&first).unwrap(), "Maya: Hello.");
+        assert!(dir.0.join("output-metadata8.txt").is_file());
+    }
+
+    #[test]
+    fn reserve_skips_claimed_numbers() {
+        let dir = TempDir::new("reserve");
+        let writer = OutputWriter::new(dir.0.clone());
+
+        assert_eq!(writer.reserve_file_number().unwrap(), 1);
+        assert_eq!(writer.reserve_file_number().unwrap(), 2);
+        assert!(dir.0.join("generated-dialog2.txt").is_file());
+    }
+
+    #[test]
+    fn edit_versions_are_numbered_by_source() {
+        let dir = TempDir::new("edit-version");
+        let writer = OutputWriter::new(dir.0.clone());
+
+        let version = writer
+            .edit_version(&dir.0.join("generated-dialog3.txt"), false)
+            .unwrap();
+        assert_eq!(version.file_name("podcast", ""), "edited-podcast3.txt");
+        assert_eq!(version.attempt(), 1);
+
+        let version = writer
+            .edit_version(Path::new("scripts/draft.txt"), false)
+            .unwrap();
+        assert_eq!(version.file_name("podcast", ""), "edited-podcast-draft.txt");
+
+        let version = writer
+            .edit_version(Path::new("generated-dialog-old.txt"), false)
+            .unwrap();
+        assert_eq!(
+            version.file_name("podcast", ""),
+            "edited-podcast-generated-dialog-old.txt"
+        );
+    }
+
+    #[test]
+    fn kept_attempts_claim_the_next_number() {
+        let dir = TempDir::new("edit-attempts");
+        let writer = OutputWriter::new(dir.0.clone());
+        let source = dir.0.join("generated-dialog2.txt");
+
+        let first = writer.edit_version(&source, true).unwrap();
+        let second = writer.edit_version(&source, true).unwrap();
+        assert_eq!(first.file_name("podcast", ""), "edited-podcast2.txt");
+        assert_eq!(second.file_name("podcast", ""), "edited-podcast2-2.txt");
+        assert_eq!(second.attempt(), 2);
+
+        // Without keep_attempts the first attempt is overwritten
+        let again = writer.edit_version(&source, false).unwrap();
+        assert_eq!(again.attempt(), 1);
+
+        let path = writer.write_edited(&second, &dialog()).unwrap();
+        let metadata = fs::read_to_string(dir.0.join("edited-metadata2-2.txt")).unwrap();
+        assert_eq!(path, dir.0.join("edited-podcast2-2.txt"));
+        assert!(metadata.starts_with("Source: generated-dialog2.txt\nAttempt: 2\n"));
+    }
+
+    #[test]
+    fn kept_attempts_give_up_when_every_number_is_taken() {
+        let dir = TempDir::new("edit-attempts-full");
+        let writer = OutputWriter::new(dir.0.clone());
+        fs::write(dir.0.join("edited-podcast2.txt"), "").unwrap();
+        for attempt in 2..=MAX_RESERVE_ATTEMPTS {
+            fs::write(dir.0.join(format!("edited-podcast2-{}.txt", attempt)), "").unwrap();
+        }
+
+        let result = writer.edit_version(&dir.0.join("generated-dialog2.txt"), true);
+        assert!(matches!(
+            result,
+            Err(DialogGenError::Io(e)) if e.kind() == io::ErrorKind::AlreadyExists
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn new_run_reports_a_latest_link_it_cannot_replace() {
+        let dir = TempDir::new("latest-link");
+        let first = OutputWriter::new_run(&dir.0, "llama3.2:latest").unwrap();
+        assert!(first.latest_link_error().is_none());
+        assert_eq!(
+            fs::read_link(dir.0.join(LATEST_LINK)).unwrap(),
+            first.output_dir().file_name().unwrap()
+        );
+
+        // A real directory where the link should be is left alone
+        fs::remove_file(dir.0.join(LATEST_LINK)).unwrap();
+        fs::create_dir(dir.0.join(LATEST_LINK)).unwrap();
+        fs::write(dir.0.join(LATEST_LINK).join("keep.txt"), "").unwrap();
+        let second = OutputWriter::new_run(&dir.0, "llama3.2:latest").unwrap();
+
+        assert!(second.output_dir().is_dir());
+        assert!(
+            second
+                .latest_link_error()
+                .is_some_and(|e| e.starts_with("could not point"))
+        );
+    }
+
+    #[test]
+    fn model_labels_are_path_safe() {
+        assert_eq!(run_model_label("llama3.2:latest"), "llama3.2");
+        assert_eq!(run_model_label("mistral:7b"), "mistral-7b");
+        assert_eq!(run_model_label("org/model"), "org-model");
     }
 }
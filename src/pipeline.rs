@@ -0,0 +1,345 @@
+//! Multi-pass editing pipeline
+//!
+//! An `editors/` directory holds one file per pass, run in file-name order
+//! (e.g. `01-tighten.txt`, `02-punch-up.txt`). A file is an optional header
+//! of `Key: value` lines, a `---` line, then the pass's editor prompt:
+//!
+//! ```text
+//! Model: llama3.2:latest
+//! Temperature: 0.4
+//! Mode: lines
+//! ---
+//! You are a script editor. Cut every line down to its essentials...
+//! ```
+//!
+//! Without a `---` line the whole file is the prompt. Passes without a
+//! model, temperature or mode use the run's defaults.
+//!
+//! [`edit_and_save`] is the whole editing step of a run: the scene's passes
+//! (or the built-in editor when there are none), the edited files and the
+//! fidelity check against the original.
+
+use crate::config::Speaker;
+use crate::editor::{DEFAULT_EDIT_TEMPERATURE, EditMode, PodcastEditor};
+use crate::error::{DialogGenError, Result};
+use crate::events::GenerationObserver;
+use crate::fidelity::{self, FidelityOptions, FidelityReport};
+use crate::ollama::OllamaClient;
+use crate::orchestrator::GeneratedDialog;
+use crate::output::OutputWriter;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Directory inside a scene that defines editing passes
+pub const EDITORS_DIR: &str = "editors";
+
+/// One editing pass loaded from an editors directory
+#[derive(Debug, Clone)]
+pub struct EditorPassConfig {
+    /// File name without its order prefix, e.g. "tighten"
+    pub name: String,
+    pub system_prompt: String,
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub mode: Option<EditMode>,
+}
+
+/// How a dialog is edited, saved and checked
+#[derive(Debug, Clone, Default)]
+pub struct EditSettings {
+    /// Mode for the built-in editor and passes that don't set one
+    pub mode: EditMode,
+    /// Passes to run; defaults to the scene's `editors/` when it has one
+    pub editors: Option<PathBuf>,
+    /// Keep earlier edits of the same dialog instead of overwriting them
+    pub keep_edits: bool,
+    pub fidelity: FidelityOptions,
+}
+
+/// A dialog about to be edited
+pub struct EditSource<'a> {
+    pub dialog: &'a GeneratedDialog,
+    /// File the dialog was read from or saved as
+    pub path: &'a Path,
+    pub speakers: &'a [Speaker],
+    /// Scene whose editors/ directory, if any, defines the passes
+    pub scene_dir: Option<&'a Path>,
+}
+
+/// An edit written to disk along with its fidelity check
+pub struct EditOutcome {
+    pub dialog: GeneratedDialog,
+    pub path: PathBuf,
+    /// Name and file of each pass's output, when passes were run
+    pub passes: Vec<(String, PathBuf)>,
+    pub report: FidelityReport,
+    pub diff_path: PathBuf,
+}
+
+/// Load the passes in `dir`, ordered by file name
+pub fn load_passes(dir: &Path) -> Result<Vec<EditorPassConfig>> {
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    paths.sort();
+
+    paths.iter().map(|path| load_pass(path)).collect()
+}
+
+fn load_pass(path: &Path) -> Result<EditorPassConfig> {
+    let content = fs::read_to_string(path)?;
+    let file = path.display().to_string();
+    let invalid = |message: String| DialogGenError::InvalidConfig(format!("{}: {}", file, message));
+
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = stem
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .trim_start_matches(['-', '_'])
+        .to_string();
+
+    let lines: Vec<&str> = content.lines().collect();
+    let (header, prompt) = match lines.iter().position(|l| l.trim() == "---") {
+        Some(pos) => (&lines[..pos], &lines[pos + 1..]),
+        None => (&lines[..0], &lines[..]),
+    };
+
+    let mut pass = EditorPassConfig {
+        name: if name.is_empty() { stem } else { name },
+        system_prompt: prompt.join("\n").trim().to_string(),
+        model: None,
+        temperature: None,
+        mode: None,
+    };
+
+    for line in header.iter().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+        let Some((key, value)) = line.split_once(':') else {
+            return Err(invalid(format!("expected 'Key: value', got '{}'", line)));
+        };
+        let value = value.trim();
+        match key.trim().to_lowercase().as_str() {
+            "model" => pass.model = Some(value.to_string()),
+            "temperature" => {
+                pass.temperature = Some(
+                    value
+                        .parse()
+                        .map_err(|_| invalid(format!("bad temperature '{}'", value)))?,
+                )
+            }
+            "mode" => {
+                pass.mode = Some(
+                    value
+                        .parse::<EditMode>()
+                        .map_err(|_| invalid(format!("unknown mode '{}'", value)))?,
+                )
+            }
+            other => return Err(invalid(format!("unknown key '{}'", other))),
+        }
+    }
+
+    if pass.system_prompt.is_empty() {
+        return Err(invalid("missing editor prompt".to_string()));
+    }
+    Ok(pass)
+}
+
+/// Run the passes in order, each on the previous pass's output. Returns
+/// every intermediate result; the last one is the final edit.
+pub async fn run(
+    passes: &[EditorPassConfig],
+    ollama: &OllamaClient,
+    dialog: &GeneratedDialog,
+    speakers: &[Speaker],
+    default_mode: EditMode,
+    observer: Option<Arc<dyn GenerationObserver>>,
+) -> Result<Vec<GeneratedDialog>> {
+    let mut results: Vec<GeneratedDialog> = Vec::new();
+
+    for pass in passes {
+        let backend = match &pass.model {
+            Some(model) => ollama.with_model(model),
+            None => ollama.clone(),
+        };
+        let mut editor = PodcastEditor::new(backend)
+            .with_name(&pass.name)
+            .with_system_prompt(&pass.system_prompt)
+            .with_mode(pass.mode.unwrap_or(default_mode))
+            .with_temperature(pass.temperature.unwrap_or(DEFAULT_EDIT_TEMPERATURE))
+            .with_speakers(speakers.to_vec());
+        if let Some(observer) = &observer {
+            editor = editor.with_observer(observer.clone());
+        }
+
+        let input = results.last().unwrap_or(dialog);
+        let edited = editor.edit(input).await?;
+        results.push(edited);
+    }
+
+    Ok(results)
+}
+
+/// Run the podcast editor, or the scene's editing passes when it has an
+/// editors directory, then write the edit and its fidelity diff. Nothing is
+/// written unless every pass succeeds.
+pub async fn edit_and_save(
+    source: EditSource<'_>,
+    ollama: &OllamaClient,
+    settings: &EditSettings,
+    writer: &OutputWriter,
+    observer: Option<Arc<dyn GenerationObserver>>,
+) -> Result<EditOutcome> {
+    let EditSource {
+        dialog,
+        path,
+        speakers,
+        scene_dir,
+    } = source;
+    let editors_dir = settings
+        .editors
+        .clone()
+        .or_else(|| scene_dir.map(|dir| dir.join(EDITORS_DIR)));
+    let passes = match &editors_dir {
+        Some(dir) if dir.is_dir() => load_passes(dir)?,
+        Some(dir) if settings.editors.is_some() => {
+            return Err(DialogGenError::InvalidConfig(format!(
+                "Editors directory not found: {}",
+                dir.display()
+            )));
+        }
+        _ => Vec::new(),
+    };
+
+    let mut results = if passes.is_empty() {
+        let mut editor = PodcastEditor::new(ollama.clone())
+            .with_mode(settings.mode)
+            .with_speakers(speakers.to_vec());
+        if let Some(observer) = observer {
+            editor = editor.with_observer(observer);
+        }
+        vec![editor.edit(dialog).await?]
+    } else {
+        run(&passes, ollama, dialog, speakers, settings.mode, observer).await?
+    };
+
+    // Only a finished edit claims an attempt number
+    let version = writer.edit_version(path, settings.keep_edits)?;
+    let edited = results.pop().expect("at least one pass");
+    let path = writer.write_edited(&version, &edited)?;
+    let mut pass_paths = Vec::new();
+    for (i, (pass, result)) in passes
+        .iter()
+        .zip(results.iter().chain([&edited]))
+        .enumerate()
+    {
+        let path = writer.write_edit_pass(&version, i + 1, &pass.name, result)?;
+        pass_paths.push((pass.name.clone(), path));
+    }
+    let report = fidelity::check(&dialog.exchanges, &edited.exchanges, &settings.fidelity);
+    let diff_path = writer.write_edited_diff(&version, &report)?;
+
+    Ok(EditOutcome {
+        dialog: edited,
+        path,
+        passes: pass_paths,
+        report,
+        diff_path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("dialog-gen-test-{}-{}", process::id(), name));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn write(&self, name: &str, content: &str) -> PathBuf {
+            let path = self.0.join(name);
+            fs::write(&path, content).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn load_error(dir: &TempDir, content: &str) -> String {
+        load_pass(&dir.write("01-pass.txt", content))
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn header_sets_model_temperature_and_mode() {
+        let dir = TempDir::new("pipeline-header");
+        let path = dir.write(
+            "01-tighten.txt",
+            "Model: llama3.2:latest\nTEMPERATURE: 0.4\n\nmode: Lines\n---\nCut every line.\n",
+        );
+
+        let pass = load_pass(&path).unwrap();
+        assert_eq!(pass.name, "tighten");
+        assert_eq!(pass.model.as_deref(), Some("llama3.2:latest"));
+        assert_eq!(pass.temperature, Some(0.4));
+        assert_eq!(pass.mode, Some(EditMode::Lines));
+        assert_eq!(pass.system_prompt, "Cut every line.");
+    }
+
+    #[test]
+    fn without_a_separator_the_whole_file_is_the_prompt() {
+        let dir = TempDir::new("pipeline-no-header");
+        let path = dir.write("punch-up.txt", "Model: not a header\nAdd jokes.\n");
+
+        let pass = load_pass(&path).unwrap();
+        assert_eq!(pass.name, "punch-up");
+        assert_eq!(pass.model, None);
+        assert_eq!(pass.system_prompt, "Model: not a header\nAdd jokes.");
+    }
+
+    #[test]
+    fn bad_headers_name_the_file_and_problem() {
+        let dir = TempDir::new("pipeline-bad-header");
+
+        assert!(
+            load_error(&dir, "Temperature: warm\n---\nEdit.").contains("bad temperature 'warm'")
+        );
+        assert!(load_error(&dir, "Mode: sideways\n---\nEdit.").contains("unknown mode 'sideways'"));
+        assert!(load_error(&dir, "Voice: Ava\n---\nEdit.").contains("unknown key 'voice'"));
+        assert!(load_error(&dir, "just words\n---\nEdit.").contains("expected 'Key: value'"));
+        let missing = load_error(&dir, "Model: llama3.2\n---\n  \n");
+        assert!(missing.contains("01-pass.txt"));
+        assert!(missing.contains("missing editor prompt"));
+    }
+
+    #[test]
+    fn passes_run_in_file_name_order() {
+        let dir = TempDir::new("pipeline-order");
+        dir.write("02-punch-up.txt", "Add jokes.");
+        dir.write("01-tighten.txt", "Cut lines.");
+        dir.write("notes.md", "Not a pass.");
+
+        let names: Vec<String> = load_passes(&dir.0)
+            .unwrap()
+            .into_iter()
+            .map(|pass| pass.name)
+            .collect();
+        assert_eq!(names, ["tighten", "punch-up"]);
+    }
+}
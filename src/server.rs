@@ -0,0 +1,769 @@
+//! Local HTTP API for generating dialog
+//!
+//! Routes:
+//! - `POST /generate` runs a scene and returns the dialog as JSON
+//! - `POST /generate/stream` runs a scene and streams progress as
+//!   Server-Sent Events, ending with a `dialog` (or `error`) event
+//! - `POST /jobs` starts a background run and returns its id
+//! - `GET /jobs/{id}` reports the job's state and progress
+//! - `GET /jobs/{id}/result` returns the finished dialog
+//! - `DELETE /jobs/{id}` cancels a running job
+//!
+//! Scenes are posted as JSON with the same fields as the scene directory
+//! files. Model, turns and temperature fall back to the server's settings.
+//! `ServerLimits` caps the turns a request may ask for, how many
+//! generations run at once (more get `429 Too Many Requests`), how often a
+//! turn is retried and how long finished jobs are kept.
+
+use crate::backend::ChatBackend;
+use crate::config::{DialogConfig, DialogLine, Directions, Speaker};
+use crate::error::DialogGenError;
+use crate::events::GenerationEvent;
+use crate::ollama::{ChatStats, OllamaClient};
+use crate::orchestrator::{DialogOrchestrator, GeneratedDialog};
+use crate::settings::Settings;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, mpsc};
+use tokio::task::AbortHandle;
+use tokio_stream::StreamExt;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+/// A scene posted to the API
+#[derive(Debug, Deserialize)]
+pub struct SceneRequest {
+    pub speaker1: Speaker,
+    pub speaker2: Speaker,
+    #[serde(default)]
+    pub directions: Directions,
+    #[serde(default)]
+    pub initial_lines: Vec<DialogLine>,
+    pub model: Option<String>,
+    pub turns: Option<usize>,
+    pub temperature: Option<f32>,
+    pub seed: Option<u64>,
+}
+
+/// A generated dialog as returned by the API
+#[derive(Debug, Clone, Serialize)]
+pub struct DialogResponse {
+    pub lines: Vec<LineResponse>,
+    pub model: String,
+    pub turns: usize,
+    pub temperature: f32,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub wall_time_ms: u128,
+    pub tokens_per_second: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LineResponse {
+    pub speaker: String,
+    pub content: String,
+    /// False for seed lines from the request
+    pub generated: bool,
+}
+
+impl From<&GeneratedDialog> for DialogResponse {
+    fn from(dialog: &GeneratedDialog) -> Self {
+        let metadata = &dialog.metadata;
+        Self {
+            lines: dialog
+                .exchanges
+                .iter()
+                .map(|e| LineResponse {
+                    speaker: e.speaker.clone(),
+                    content: e.content.clone(),
+                    generated: e.stats.is_some(),
+                })
+                .collect(),
+            model: metadata.model.clone(),
+            turns: metadata.turns,
+            temperature: metadata.temperature,
+            prompt_tokens: metadata.total_prompt_tokens,
+            completion_tokens: metadata.total_completion_tokens,
+            wall_time_ms: metadata.total_wall_time.as_millis(),
+            tokens_per_second: metadata.avg_tokens_per_second,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Progress of a background job
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub id: u64,
+    pub state: JobState,
+    pub turns_completed: usize,
+    pub turns_total: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Bounds on what clients can make the server do and keep
+#[derive(Debug, Clone, Copy)]
+pub struct ServerLimits {
+    /// Most turns a single request may ask for
+    pub max_turns: usize,
+    /// Most generations running at once, counting every route
+    pub max_running: usize,
+    /// Retries allowed for a turn that fails or comes back empty
+    pub max_retries: u32,
+    /// Finished, failed and cancelled jobs are forgotten after this long
+    pub job_ttl: Duration,
+    /// Most finished jobs kept; the oldest are forgotten first
+    pub max_finished_jobs: usize,
+}
+
+impl Default for ServerLimits {
+    fn default() -> Self {
+        Self {
+            max_turns: 100,
+            max_running: 4,
+            max_retries: 0,
+            job_ttl: Duration::from_secs(60 * 60),
+            max_finished_jobs: 1000,
+        }
+    }
+}
+
+struct Job {
+    status: JobStatus,
+    result: Option<DialogResponse>,
+    abort: Option<AbortHandle>,
+    /// When the job stopped running
+    finished_at: Option<Instant>,
+}
+
+impl Job {
+    fn finish(&mut self, state: JobState) {
+        self.status.state = state;
+        self.abort = None;
+        self.finished_at = Some(Instant::now());
+    }
+}
+
+/// Makes the backend that runs a scene for the requested model
+type BackendFactory<B> = dyn Fn(&str) -> B + Send + Sync;
+
+struct AppState<B> {
+    settings: Arc<Settings>,
+    limits: ServerLimits,
+    backend: Arc<BackendFactory<B>>,
+    /// One permit per generation allowed to run at once
+    running: Arc<Semaphore>,
+    jobs: Arc<Mutex<HashMap<u64, Job>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl<B> Clone for AppState<B> {
+    fn clone(&self) -> Self {
+        Self {
+            settings: Arc::clone(&self.settings),
+            limits: self.limits,
+            backend: Arc::clone(&self.backend),
+            running: Arc::clone(&self.running),
+            jobs: Arc::clone(&self.jobs),
+            next_id: Arc::clone(&self.next_id),
+        }
+    }
+}
+
+/// An error response with a JSON `{"error": ...}` body
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<DialogGenError> for ApiError {
+    fn from(e: DialogGenError) -> Self {
+        let status = match e {
+            DialogGenError::InvalidConfig(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            DialogGenError::OllamaUnavailable(_) | DialogGenError::Http(_) => {
+                StatusCode::BAD_GATEWAY
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        ApiError(status, e.to_string())
+    }
+}
+
+/// Build the API router. `settings` supplies the Ollama URL and the
+/// defaults for anything a scene request leaves out.
+pub fn router(settings: Settings, limits: ServerLimits) -> Router {
+    let ollama_url = settings.ollama_url.value.clone();
+    router_with_backend(settings, limits, move |model| {
+        OllamaClient::new(&ollama_url, model)
+    })
+}
+
+/// Like [`router`], but scenes run against the backend `backend` returns
+/// for the requested model instead of Ollama
+pub fn router_with_backend<B, F>(settings: Settings, limits: ServerLimits, backend: F) -> Router
+where
+    B: ChatBackend + 'static,
+    F: Fn(&str) -> B + Send + Sync + 'static,
+{
+    let state = AppState {
+        settings: Arc::new(settings),
+        limits,
+        backend: Arc::new(backend),
+        running: Arc::new(Semaphore::new(limits.max_running)),
+        jobs: Arc::new(Mutex::new(HashMap::new())),
+        next_id: Arc::new(AtomicU64::new(1)),
+    };
+
+    Router::new()
+        .route("/health", get(|| async { "ok" }))
+        .route("/generate", post(generate::<B>))
+        .route("/generate/stream", post(generate_stream::<B>))
+        .route("/jobs", post(submit_job::<B>))
+        .route("/jobs/{id}", get(job_status::<B>).delete(cancel_job::<B>))
+        .route("/jobs/{id}/result", get(job_result::<B>))
+        .with_state(state)
+}
+
+/// Serve the API until the process is stopped
+pub async fn serve(
+    listener: tokio::net::TcpListener,
+    settings: Settings,
+    limits: ServerLimits,
+) -> std::io::Result<()> {
+    axum::serve(listener, router(settings, limits)).await
+}
+
+impl<B: ChatBackend> AppState<B> {
+    /// Turn a scene request into an orchestrator, applying server defaults
+    fn orchestrator(&self, scene: SceneRequest) -> Result<DialogOrchestrator<B>, ApiError> {
+        let settings = &self.settings;
+        let turns = self.turns(&scene)?;
+        let model = scene.model.unwrap_or_else(|| settings.model.value.clone());
+
+        let mut builder = DialogConfig::builder()
+            .speaker1(scene.speaker1)
+            .speaker2(scene.speaker2)
+            .directions(scene.directions)
+            .model(&model)
+            .turns(turns)
+            .temperature(scene.temperature.unwrap_or(settings.temperature.value));
+        for line in scene.initial_lines {
+            builder = builder.line(line.speaker, line.content);
+        }
+        if let Some(seed) = scene.seed {
+            builder = builder.seed(seed);
+        }
+        let config = builder.build()?;
+
+        Ok(DialogOrchestrator::new((self.backend)(&model), config)
+            .with_max_retries(self.limits.max_retries))
+    }
+
+    /// Claim a slot for a generation; held until the permit is dropped
+    fn start_run(&self) -> Result<OwnedSemaphorePermit, ApiError> {
+        Arc::clone(&self.running).try_acquire_owned().map_err(|_| {
+            ApiError(
+                StatusCode::TOO_MANY_REQUESTS,
+                format!(
+                    "{} generations are already running; try again later",
+                    self.limits.max_running
+                ),
+            )
+        })
+    }
+
+    /// Turns the scene will run, within the server's limit
+    fn turns(&self, scene: &SceneRequest) -> Result<usize, ApiError> {
+        let turns = scene.turns.unwrap_or(self.settings.turns.value);
+        if turns > self.limits.max_turns {
+            return Err(ApiError(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "turns is {}; this server allows at most {}",
+                    turns, self.limits.max_turns
+                ),
+            ));
+        }
+        Ok(turns)
+    }
+
+    /// Forget finished jobs past their time to live, then the oldest
+    /// finished jobs beyond the cap
+    fn prune_jobs(&self, jobs: &mut HashMap<u64, Job>) {
+        let limits = &self.limits;
+        jobs.retain(|_, job| {
+            job.finished_at
+                .is_none_or(|finished| finished.elapsed() < limits.job_ttl)
+        });
+
+        let mut finished: Vec<(Instant, u64)> = jobs
+            .iter()
+            .filter_map(|(id, job)| job.finished_at.map(|at| (at, *id)))
+            .collect();
+        if finished.len() > limits.max_finished_jobs {
+            finished.sort();
+            let excess = finished.len() - limits.max_finished_jobs;
+            for (_, id) in &finished[..excess] {
+                jobs.remove(id);
+            }
+        }
+    }
+}
+
+async fn generate<B: ChatBackend + 'static>(
+    State(state): State<AppState<B>>,
+    Json(scene): Json<SceneRequest>,
+) -> Result<Json<DialogResponse>, ApiError> {
+    let orchestrator = state.orchestrator(scene)?;
+    let _permit = state.start_run()?;
+    let dialog = orchestrator.generate().await?;
+    Ok(Json(DialogResponse::from(&dialog)))
+}
+
+async fn generate_stream<B: ChatBackend + 'static>(
+    State(state): State<AppState<B>>,
+    Json(scene): Json<SceneRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    let events = tx.clone();
+    let observer = move |event: GenerationEvent| {
+        if let Some(event) = sse_event(&event) {
+            let _ = events.send(event);
+        }
+    };
+    let orchestrator = state.orchestrator(scene)?.with_observer(Arc::new(observer));
+    let permit = state.start_run()?;
+
+    tokio::spawn(async move {
+        let _permit = permit;
+        // Stop generating once the client disconnects
+        let result = tokio::select! {
+            result = orchestrator.generate() => result,
+            _ = tx.closed() => return,
+        };
+        let event = match result {
+            Ok(dialog) => json_event("dialog", &DialogResponse::from(&dialog)),
+            Err(e) => json_event("error", &serde_json::json!({ "error": e.to_string() })),
+        };
+        let _ = tx.send(event);
+    });
+
+    let stream = UnboundedReceiverStream::new(rx).map(Ok::<_, Infallible>);
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+fn json_event(name: &str, data: &impl Serialize) -> Event {
+    Event::default()
+        .event(name)
+        .json_data(data)
+        .expect("API types serialize to JSON")
+}
+
+fn stats_json(stats: &ChatStats) -> serde_json::Value {
+    serde_json::json!({
+        "prompt_tokens": stats.prompt_tokens,
+        "completion_tokens": stats.completion_tokens,
+        "wall_time_ms": stats.wall_time.as_millis(),
+        "tokens_per_second": stats.tokens_per_second(),
+        "retries": stats.retries,
+    })
+}
+
+/// Map a progress event to an SSE event; run-level events are covered by
+/// the final `dialog` event
+fn sse_event(event: &GenerationEvent) -> Option<Event> {
+    use serde_json::json;
+
+    let event = match event {
+        GenerationEvent::TurnStarted {
+            turn,
+            total,
+            speaker,
+        } => json_event(
+            "turn_started",
+            &json!({ "turn": turn, "total": total, "speaker": speaker }),
+        ),
+        GenerationEvent::Token { turn, text } => {
+            json_event("token", &json!({ "turn": turn, "text": text }))
+        }
+        GenerationEvent::Retry {
+            turn,
+            retry,
+            max_retries,
+            error,
+        } => json_event(
+            "retry",
+            &json!({
+                "turn": turn,
+                "retry": retry,
+                "max_retries": max_retries,
+                "error": error,
+            }),
+        ),
+        GenerationEvent::TurnCompleted {
+            turn,
+            speaker,
+            content,
+            stats,
+        } => json_event(
+            "turn_completed",
+            &json!({
+                "turn": turn,
+                "speaker": speaker,
+                "content": content,
+                "stats": stats_json(stats),
+            }),
+        ),
+        _ => return None,
+    };
+    Some(event)
+}
+
+async fn submit_job<B: ChatBackend + 'static>(
+    State(state): State<AppState<B>>,
+    Json(scene): Json<SceneRequest>,
+) -> Result<(StatusCode, Json<JobStatus>), ApiError> {
+    let turns_total = state.turns(&scene)?;
+    let id = state.next_id.fetch_add(1, Ordering::Relaxed);
+
+    let jobs = state.jobs.clone();
+    let observer = move |event: GenerationEvent| {
+        if let GenerationEvent::TurnCompleted { turn, .. } = event
+            && let Some(job) = jobs.lock().unwrap().get_mut(&id)
+        {
+            job.status.turns_completed = turn;
+        }
+    };
+    let orchestrator = state.orchestrator(scene)?.with_observer(Arc::new(observer));
+    let permit = state.start_run()?;
+
+    let status = JobStatus {
+        id,
+        state: JobState::Running,
+        turns_completed: 0,
+        turns_total,
+        error: None,
+    };
+
+    // Register the job before spawning so a fast run can't finish first
+    let mut jobs = state.jobs.lock().unwrap();
+    state.prune_jobs(&mut jobs);
+    jobs.insert(
+        id,
+        Job {
+            status: status.clone(),
+            result: None,
+            abort: None,
+            finished_at: None,
+        },
+    );
+
+    let finished = state.jobs.clone();
+    let handle = tokio::spawn(async move {
+        let result = orchestrator.generate().await;
+        drop(permit);
+        let mut jobs = finished.lock().unwrap();
+        let Some(job) = jobs.get_mut(&id) else {
+            return;
+        };
+        match result {
+            Ok(dialog) => {
+                job.finish(JobState::Completed);
+                job.result = Some(DialogResponse::from(&dialog));
+            }
+            Err(e) => {
+                job.finish(JobState::Failed);
+                job.status.error = Some(e.to_string());
+            }
+        }
+    });
+    if let Some(job) = jobs.get_mut(&id) {
+        job.abort = Some(handle.abort_handle());
+    }
+
+    Ok((StatusCode::ACCEPTED, Json(status)))
+}
+
+fn not_found(id: u64) -> ApiError {
+    ApiError(StatusCode::NOT_FOUND, format!("no job {}", id))
+}
+
+async fn job_status<B: ChatBackend>(
+    State(state): State<AppState<B>>,
+    Path(id): Path<u64>,
+) -> Result<Json<JobStatus>, ApiError> {
+    let mut jobs = state.jobs.lock().unwrap();
+    state.prune_jobs(&mut jobs);
+    let job = jobs.get(&id).ok_or_else(|| not_found(id))?;
+    Ok(Json(job.status.clone()))
+}
+
+async fn job_result<B: ChatBackend>(
+    State(state): State<AppState<B>>,
+    Path(id): Path<u64>,
+) -> Result<Json<DialogResponse>, ApiError> {
+    let mut jobs = state.jobs.lock().unwrap();
+    state.prune_jobs(&mut jobs);
+    let job = jobs.get(&id).ok_or_else(|| not_found(id))?;
+    match &job.result {
+        Some(result) => Ok(Json(result.clone())),
+        None => Err(ApiError(
+            StatusCode::CONFLICT,
+            format!("job {} is {:?}", id, job.status.state).to_lowercase(),
+        )),
+    }
+}
+
+async fn cancel_job<B: ChatBackend>(
+    State(state): State<AppState<B>>,
+    Path(id): Path<u64>,
+) -> Result<Json<JobStatus>, ApiError> {
+    let mut jobs = state.jobs.lock().unwrap();
+    let job = jobs.get_mut(&id).ok_or_else(|| not_found(id))?;
+    if let Some(abort) = job.abort.take() {
+        abort.abort();
+        job.finish(JobState::Cancelled);
+    }
+    Ok(Json(job.status.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ollama::{ChatMessage, ChatOptions, ChatResult};
+    use serde_json::{Value, json};
+
+    /// Replies "Sounds good." after `delay`; the "down" model fails as if
+    /// Ollama weren't running
+    struct TestBackend {
+        model: String,
+        delay: Duration,
+    }
+
+    impl ChatBackend for TestBackend {
+        fn model(&self) -> &str {
+            &self.model
+        }
+
+        async fn chat(
+            &self,
+            _system_prompt: &str,
+            _messages: &[ChatMessage],
+            _options: &ChatOptions,
+        ) -> crate::error::Result<ChatResult> {
+            tokio::time::sleep(self.delay).await;
+            if self.model == "down" {
+                return Err(DialogGenError::OllamaUnavailable(
+                    "http://localhost:11434".to_string(),
+                ));
+            }
+            Ok(ChatResult {
+                content: "Sounds good.".to_string(),
+                stats: ChatStats::default(),
+            })
+        }
+    }
+
+    /// Serve the API on a free local port and return its base URL. The
+    /// "slow" model takes 300ms per turn.
+    async fn start(limits: ServerLimits) -> String {
+        let app = router_with_backend(Settings::resolve([]), limits, |model: &str| TestBackend {
+            model: model.to_string(),
+            delay: Duration::from_millis(if model == "slow" { 300 } else { 0 }),
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    fn scene(model: &str, turns: usize) -> Value {
+        json!({
+            "speaker1": {"name": "Maya"},
+            "speaker2": {"name": "Dev"},
+            "initial_lines": [{"speaker": "Maya", "content": "Ready?"}],
+            "model": model,
+            "turns": turns,
+        })
+    }
+
+    async fn post(url: &str, body: &Value) -> (StatusCode, Value) {
+        let response = reqwest::Client::new()
+            .post(url)
+            .json(body)
+            .send()
+            .await
+            .unwrap();
+        let status = StatusCode::from_u16(response.status().as_u16()).unwrap();
+        (status, response.json().await.unwrap())
+    }
+
+    async fn get_json(url: &str) -> (StatusCode, Value) {
+        let response = reqwest::get(url).await.unwrap();
+        let status = StatusCode::from_u16(response.status().as_u16()).unwrap();
+        (status, response.json().await.unwrap())
+    }
+
+    /// Poll a job until it stops running
+    async fn wait_for(base: &str, id: u64) -> Value {
+        loop {
+            let (_, status) = get_json(&format!("{}/jobs/{}", base, id)).await;
+            if status["state"] != "running" {
+                return status;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn generates_a_dialog() {
+        let base = start(ServerLimits::default()).await;
+        let (status, body) = post(&format!("{}/generate", base), &scene("test", 2)).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["model"], "test");
+        assert_eq!(body["turns"], 2);
+        let lines = body["lines"].as_array().unwrap();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["generated"], false);
+        assert_eq!(lines[1]["speaker"], "Dev");
+        assert_eq!(lines[1]["content"], "Sounds good.");
+    }
+
+    #[tokio::test]
+    async fn maps_errors_to_status_codes() {
+        let limits = ServerLimits {
+            max_turns: 5,
+            ..Default::default()
+        };
+        let base = start(limits).await;
+        let url = format!("{}/generate", base);
+
+        let (status, body) = post(&url, &scene("test", 6)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"], "turns is 6; this server allows at most 5");
+
+        let mut same_names = scene("test", 2);
+        same_names["speaker2"]["name"] = json!("Maya");
+        let (status, _) = post(&url, &same_names).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, body) = post(&url, &scene("down", 2)).await;
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+        assert!(body["error"].as_str().unwrap().contains("unavailable"));
+    }
+
+    #[tokio::test]
+    async fn runs_background_jobs() {
+        let base = start(ServerLimits::default()).await;
+
+        let (status, job) = post(&format!("{}/jobs", base), &scene("slow", 1)).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(job["state"], "running");
+        assert_eq!(job["turns_total"], 1);
+        let id = job["id"].as_u64().unwrap();
+
+        let (status, _) = get_json(&format!("{}/jobs/{}/result", base, id)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let finished = wait_for(&base, id).await;
+        assert_eq!(finished["state"], "completed");
+        assert_eq!(finished["turns_completed"], 1);
+
+        let (status, result) = get_json(&format!("{}/jobs/{}/result", base, id)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(result["lines"].as_array().unwrap().len(), 2);
+
+        let (status, _) = get_json(&format!("{}/jobs/999", base)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn failed_jobs_report_the_error() {
+        let base = start(ServerLimits::default()).await;
+        let (_, job) = post(&format!("{}/jobs", base), &scene("down", 1)).await;
+
+        let finished = wait_for(&base, job["id"].as_u64().unwrap()).await;
+        assert_eq!(finished["state"], "failed");
+        assert!(finished["error"].as_str().unwrap().contains("unavailable"));
+    }
+
+    #[tokio::test]
+    async fn streams_progress_events() {
+        let base = start(ServerLimits::default()).await;
+        let body = reqwest::Client::new()
+            .post(format!("{}/generate/stream", base))
+            .json(&scene("test", 2))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        let events: Vec<&str> = body
+            .lines()
+            .filter_map(|line| line.strip_prefix("event: "))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                "turn_started",
+                "token",
+                "turn_completed",
+                "turn_started",
+                "token",
+                "turn_completed",
+                "dialog"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn limits_running_generations() {
+        let limits = ServerLimits {
+            max_running: 1,
+            ..Default::default()
+        };
+        let base = start(limits).await;
+
+        let (status, job) = post(&format!("{}/jobs", base), &scene("slow", 1)).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+
+        let (status, body) = post(&format!("{}/jobs", base), &scene("test", 1)).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            body["error"],
+            "1 generations are already running; try again later"
+        );
+        let (status, _) = post(&format!("{}/generate", base), &scene("test", 1)).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+        // The slot is free again once the job finishes
+        wait_for(&base, job["id"].as_u64().unwrap()).await;
+        let (status, _) = post(&format!("{}/generate", base), &scene("test", 1)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
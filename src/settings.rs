@@ -0,0 +1,295 @@
+//! Layered run settings
+//!
+//! Each setting is resolved from, lowest to highest precedence: built-in
+//! defaults, the user config file (`~/.config/dialog-gen/config.toml`),
+//! the scene's `scene.txt`, `DIALOG_GEN_*` environment variables and CLI
+//! flags. The source of every effective value is kept so `config show`
+//! can explain it.
+
+use crate::config::DialogConfig;
+use crate::error::{DialogGenError, Result};
+use serde::Deserialize;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub const DEFAULT_MODEL: &str = "mistral:7b";
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+pub const DEFAULT_TEMPERATURE: f32 = 0.7;
+pub const DEFAULT_TURNS: usize = 4;
+
+const ENV_MODEL: &str = "DIALOG_GEN_MODEL";
+const ENV_OLLAMA_URL: &str = "DIALOG_GEN_OLLAMA_URL";
+const ENV_TEMPERATURE: &str = "DIALOG_GEN_TEMPERATURE";
+const ENV_TURNS: &str = "DIALOG_GEN_TURNS";
+
+/// Where an effective setting came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Default,
+    UserConfig(PathBuf),
+    Scene,
+    Env(&'static str),
+    Cli,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::UserConfig(path) => write!(f, "{}", path.display()),
+            Source::Scene => write!(f, "scene.txt"),
+            Source::Env(var) => write!(f, "env {}", var),
+            Source::Cli => write!(f, "command line"),
+        }
+    }
+}
+
+/// An effective value and where it came from
+#[derive(Debug, Clone)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: Source,
+}
+
+/// One layer of optional values; unset fields fall through to lower layers
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SettingsLayer {
+    pub model: Option<String>,
+    pub ollama_url: Option<String>,
+    pub temperature: Option<f32>,
+    pub turns: Option<usize>,
+}
+
+impl SettingsLayer {
+    /// Read the user config file, if it exists
+    pub fn from_user_config(path: &Path) -> Result<Option<Self>> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        toml::from_str(&content)
+            .map(Some)
+            .map_err(|e| DialogGenError::InvalidConfig(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Read the model, turns and temperature set in the scene's scene.txt
+    pub fn from_scene(input_dir: &Path) -> Result<Self> {
+        let content = fs::read_to_string(input_dir.join("scene.txt"))
+            .map_err(|_| DialogGenError::MissingFile("scene.txt".to_string()))?;
+
+        let fields = DialogConfig::parse_key_value(&content);
+
+        Ok(Self {
+            model: fields.get("model").filter(|m| !m.is_empty()).cloned(),
+            ollama_url: None,
+            temperature: fields.get("temperature").and_then(|s| s.parse().ok()),
+            turns: fields.get("turns").and_then(|s| s.parse().ok()),
+        })
+    }
+}
+
+fn env_var<T: FromStr>(name: &str) -> Result<Option<T>> {
+    match env::var(name) {
+        Ok(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| DialogGenError::InvalidConfig(format!("{}={}", name, value))),
+        _ => Ok(None),
+    }
+}
+
+/// Effective settings for a run
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub model: Setting<String>,
+    pub ollama_url: Setting<String>,
+    pub temperature: Setting<f32>,
+    pub turns: Setting<usize>,
+}
+
+impl Settings {
+    /// Resolve all layers for a scene. `input_dir` is optional so settings
+    /// can be shown without a scene.
+    pub fn load(input_dir: Option<&Path>, cli: SettingsLayer) -> Result<Self> {
+        let mut layers = Vec::new();
+
+        if let Some(path) = Self::user_config_path()
+            && let Some(layer) = SettingsLayer::from_user_config(&path)?
+        {
+            layers.push((Source::UserConfig(path), layer));
+        }
+
+        if let Some(dir) = input_dir {
+            layers.push((Source::Scene, SettingsLayer::from_scene(dir)?));
+        }
+
+        // Each variable is its own layer so it can be reported by name
+        layers.extend([
+            (
+                Source::Env(ENV_MODEL),
+                SettingsLayer {
+                    model: env_var(ENV_MODEL)?,
+                    ..Default::default()
+                },
+            ),
+            (
+                Source::Env(ENV_OLLAMA_URL),
+                SettingsLayer {
+                    ollama_url: env_var(ENV_OLLAMA_URL)?,
+                    ..Default::default()
+                },
+            ),
+            (
+                Source::Env(ENV_TEMPERATURE),
+                SettingsLayer {
+                    temperature: env_var(ENV_TEMPERATURE)?,
+                    ..Default::default()
+                },
+            ),
+            (
+                Source::Env(ENV_TURNS),
+                SettingsLayer {
+                    turns: env_var(ENV_TURNS)?,
+                    ..Default::default()
+                },
+            ),
+            (Source::Cli, cli),
+        ]);
+
+        Ok(Self::resolve(layers))
+    }
+
+    /// Apply layers in order over the built-in defaults; later layers win
+    pub fn resolve(layers: impl IntoIterator<Item = (Source, SettingsLayer)>) -> Self {
+        let default = |value| Setting {
+            value,
+            source: Source::Default,
+        };
+        let mut settings = Self {
+            model: default(DEFAULT_MODEL.to_string()),
+            ollama_url: default(DEFAULT_OLLAMA_URL.to_string()),
+            temperature: Setting {
+                value: DEFAULT_TEMPERATURE,
+                source: Source::Default,
+            },
+            turns: Setting {
+                value: DEFAULT_TURNS,
+                source: Source::Default,
+            },
+        };
+
+        for (source, layer) in layers {
+            if let Some(value) = layer.model {
+                settings.model = Setting {
+                    value,
+                    source: source.clone(),
+                };
+            }
+            if let Some(value) = layer.ollama_url {
+                settings.ollama_url = Setting {
+                    value,
+                    source: source.clone(),
+                };
+            }
+            if let Some(value) = layer.temperature {
+                settings.temperature = Setting {
+                    value,
+                    source: source.clone(),
+                };
+            }
+            if let Some(value) = layer.turns {
+                settings.turns = Setting {
+                    value,
+                    source: source.clone(),
+                };
+            }
+        }
+
+        settings
+    }
+
+    /// `$XDG_CONFIG_HOME/dialog-gen/config.toml`, falling back to
+    /// `~/.config/dialog-gen/config.toml`
+    pub fn user_config_path() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(base.join("dialog-gen").join("config.toml"))
+    }
+
+    /// Overwrite the scene's turns, temperature and model with the effective values
+    pub fn apply(&self, config: &mut DialogConfig) {
+        config.scene.model = Some(self.model.value.clone());
+        config.scene.temperature = self.temperature.value;
+        config.scene.turns = self.turns.value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_uses_defaults_without_layers() {
+        let settings = Settings::resolve([]);
+
+        assert_eq!(settings.model.value, DEFAULT_MODEL);
+        assert_eq!(settings.turns.value, DEFAULT_TURNS);
+        assert_eq!(settings.temperature.source, Source::Default);
+    }
+
+    #[test]
+    fn later_layers_win_per_setting() {
+        let user = PathBuf::from("/home/me/.config/dialog-gen/config.toml");
+        let settings = Settings::resolve([
+            (
+                Source::UserConfig(user.clone()),
+                SettingsLayer {
+                    model: Some("llama3".to_string()),
+                    turns: Some(8),
+                    ..Default::default()
+                },
+            ),
+            (
+                Source::Scene,
+                SettingsLayer {
+                    turns: Some(6),
+                    ..Default::default()
+                },
+            ),
+            (
+                Source::Env(ENV_TEMPERATURE),
+                SettingsLayer {
+                    temperature: Some(0.2),
+                    ..Default::default()
+                },
+            ),
+            (
+                Source::Cli,
+                SettingsLayer {
+                    turns: Some(10),
+                    ..Default::default()
+                },
+            ),
+        ]);
+
+        assert_eq!(settings.model.value, "llama3");
+        assert_eq!(settings.model.source, Source::UserConfig(user));
+        assert_eq!(settings.turns.value, 10);
+        assert_eq!(settings.turns.source, Source::Cli);
+        assert_eq!(settings.temperature.value, 0.2);
+        assert_eq!(
+            settings.temperature.source.to_string(),
+            "env DIALOG_GEN_TEMPERATURE"
+        );
+        assert_eq!(settings.ollama_url.value, DEFAULT_OLLAMA_URL);
+    }
+}
@@ -0,0 +1,652 @@
+//! Full-screen terminal UI for watching and steering generation
+//!
+//! Each turn streams into the transcript and then waits for review: accept
+//! it, regenerate it, edit it inline or add a director note before
+//! regenerating. Stopping keeps the accepted lines for saving.
+
+use crate::backend::ChatBackend;
+use crate::config::{DialogConfig, Speaker};
+use crate::error::Result;
+use crate::events::GenerationEvent;
+use crate::ollama::ChatStats;
+use crate::orchestrator::{DialogExchange, DialogOrchestrator, GeneratedDialog};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+enum Mode {
+    /// A turn is streaming in
+    Generating,
+    /// The last generated line (if any) is waiting for a decision
+    Review,
+    /// Editing the pending line
+    Editing(String),
+    /// Typing a director note
+    Note(String),
+    /// The scene's turn count has been reached
+    Done,
+}
+
+/// What the event loop should do after a key press
+enum Action {
+    Continue,
+    Generate,
+    Save,
+    Quit,
+}
+
+struct App {
+    config: DialogConfig,
+    exchanges: Vec<DialogExchange>,
+    /// Finished line awaiting review
+    pending: Option<DialogExchange>,
+    streaming_speaker: String,
+    streaming: String,
+    notes: Vec<String>,
+    mode: Mode,
+    status: String,
+}
+
+/// Run an interactive session with `orchestrator`'s scene and settings
+/// (its observer is replaced by the UI). Returns the accepted dialog when
+/// the user stops and saves, or None when they quit without saving.
+pub async fn run<B: ChatBackend + 'static>(
+    orchestrator: DialogOrchestrator<B>,
+) -> Result<Option<GeneratedDialog>> {
+    let (progress_tx, progress_rx) = mpsc::unbounded_channel();
+    let orchestrator = Arc::new(orchestrator.with_observer(Arc::new(progress_tx)));
+    let config = orchestrator.config().clone();
+
+    let mut app = App {
+        exchanges: orchestrator.seed_exchanges(),
+        config,
+        pending: None,
+        streaming_speaker: String::new(),
+        streaming: String::new(),
+        notes: Vec::new(),
+        mode: Mode::Generating,
+        status: String::new(),
+    };
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app, &orchestrator, progress_rx).await;
+    ratatui::restore();
+
+    Ok(match result? {
+        true => Some(orchestrator.finish(app.exchanges)),
+        false => None,
+    })
+}
+
+/// Returns true when the session should be saved
+async fn event_loop<B: ChatBackend + 'static>(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    orchestrator: &Arc<DialogOrchestrator<B>>,
+    mut progress: mpsc::UnboundedReceiver<GenerationEvent>,
+) -> Result<bool> {
+    let mut keys = spawn_input_reader();
+    let mut task = Some(start_turn(orchestrator, app));
+
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+
+        let action = tokio::select! {
+            Some(input) = keys.recv() => match input {
+                Event::Key(key) => app.on_key(key),
+                _ => Action::Continue,
+            },
+            Some(event) = progress.recv() => {
+                app.on_progress(event);
+                Action::Continue
+            }
+            result = async { task.as_mut().expect("guarded by is_some").await }, if task.is_some() => {
+                task = None;
+                app.on_turn_finished(result.expect("turn task panicked"));
+                Action::Continue
+            }
+        };
+
+        match action {
+            Action::Continue => {}
+            Action::Generate => task = Some(start_turn(orchestrator, app)),
+            Action::Save | Action::Quit => {
+                if let Some(task) = task.take() {
+                    task.abort();
+                }
+                return Ok(matches!(action, Action::Save));
+            }
+        }
+    }
+}
+
+fn start_turn<B: ChatBackend + 'static>(
+    orchestrator: &Arc<DialogOrchestrator<B>>,
+    app: &mut App,
+) -> JoinHandle<Result<DialogExchange>> {
+    app.mode = Mode::Generating;
+    app.pending = None;
+    app.streaming.clear();
+    app.status.clear();
+
+    let orchestrator = orchestrator.clone();
+    let exchanges = app.exchanges.clone();
+    let notes = app.notes.clone();
+    tokio::spawn(async move { orchestrator.generate_line(&exchanges, &notes).await })
+}
+
+/// Read terminal input on a blocking thread until the receiver is dropped
+fn spawn_input_reader() -> mpsc::UnboundedReceiver<Event> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        while !tx.is_closed() {
+            match event::poll(Duration::from_millis(100)) {
+                Ok(true) => match event::read() {
+                    Ok(Event::Key(key)) if key.kind != KeyEventKind::Press => {}
+                    Ok(input) => {
+                        let _ = tx.send(input);
+                    }
+                    Err(_) => break,
+                },
+                Ok(false) => {}
+                Err(_) => break,
+            }
+        }
+    });
+    rx
+}
+
+impl App {
+    fn generated_turns(&self) -> usize {
+        self.exchanges.iter().filter(|e| e.stats.is_some()).count()
+    }
+
+    fn on_progress(&mut self, event: GenerationEvent) {
+        match event {
+            GenerationEvent::TurnStarted { speaker, .. } => {
+                self.streaming_speaker = speaker;
+                self.streaming.clear();
+            }
+            GenerationEvent::Token { text, .. } => self.streaming.push_str(&text),
+            GenerationEvent::Retry {
+                retry,
+                max_retries,
+                error,
+                ..
+            } => {
+                self.streaming.clear();
+                self.status = format!("Retry {}/{}: {}", retry, max_retries, error);
+            }
+            _ => {}
+        }
+    }
+
+    fn on_turn_finished(&mut self, result: Result<DialogExchange>) {
+        self.streaming.clear();
+        self.mode = Mode::Review;
+        match result {
+            Ok(line) => self.pending = Some(line),
+            Err(e) => self.status = format!("Turn failed: {}", e),
+        }
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> Action {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Action::Quit;
+        }
+
+        match &mut self.mode {
+            Mode::Editing(buffer) | Mode::Note(buffer) => match key.code {
+                KeyCode::Char(c) => buffer.push(c),
+                KeyCode::Backspace => {
+                    buffer.pop();
+                }
+                KeyCode::Enter => self.confirm_input(),
+                KeyCode::Esc => self.mode = self.idle_mode(),
+                _ => {}
+            },
+            Mode::Generating => match key.code {
+                KeyCode::Char('s') => return Action::Save,
+                KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+                _ => {}
+            },
+            Mode::Review => match key.code {
+                KeyCode::Char('a') | KeyCode::Enter => {
+                    if let Some(line) = self.pending.take() {
+                        self.exchanges.push(line);
+                        if self.generated_turns() >= self.config.scene.turns {
+                            self.mode = Mode::Done;
+                            self.status = "All turns generated".to_string();
+                        } else {
+                            return Action::Generate;
+                        }
+                    }
+                }
+                KeyCode::Char('r') => return Action::Generate,
+                KeyCode::Char('e') => {
+                    if let Some(pending) = &self.pending {
+                        self.mode = Mode::Editing(pending.content.clone());
+                    }
+                }
+                KeyCode::Char('n') => self.mode = Mode::Note(String::new()),
+                KeyCode::Char('s') => return Action::Save,
+                KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+                _ => {}
+            },
+            Mode::Done => match key.code {
+                KeyCode::Char('g') => return Action::Generate,
+                KeyCode::Char('n') => self.mode = Mode::Note(String::new()),
+                KeyCode::Char('s') | KeyCode::Enter => return Action::Save,
+                KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+                _ => {}
+            },
+        }
+        Action::Continue
+    }
+
+    /// Mode to return to after editing or typing a note
+    fn idle_mode(&self) -> Mode {
+        if self.pending.is_none() && self.generated_turns() >= self.config.scene.turns {
+            Mode::Done
+        } else {
+            Mode::Review
+        }
+    }
+
+    /// Apply the edited line or director note being typed
+    fn confirm_input(&mut self) {
+        let idle = self.idle_mode();
+        match std::mem::replace(&mut self.mode, idle) {
+            Mode::Editing(text) => {
+                if let Some(pending) = &mut self.pending
+                    && !text.trim().is_empty()
+                {
+                    pending.content = text.trim().to_string();
+                }
+            }
+            Mode::Note(text) if !text.trim().is_empty() => {
+                self.notes.push(text.trim().to_string());
+                self.status = "Note added; press r to regenerate with it".to_string();
+            }
+            _ => {}
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [main, stats, help] = Layout::vertical([
+            Constraint::Min(5),
+            Constraint::Length(4),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [profiles, transcript] =
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+                .areas(main);
+
+        frame.render_widget(self.profiles(), profiles);
+        self.draw_transcript(frame, transcript);
+
+        match &self.mode {
+            Mode::Editing(buffer) => frame.render_widget(
+                input_box("Edit line (Enter to save, Esc to cancel)", buffer),
+                stats,
+            ),
+            Mode::Note(buffer) => frame.render_widget(
+                input_box("Director note (Enter to add, Esc to cancel)", buffer),
+                stats,
+            ),
+            _ => frame.render_widget(self.stats(), stats),
+        }
+
+        frame.render_widget(Paragraph::new(self.help_line()), help);
+    }
+
+    fn profiles(&self) -> Paragraph<'_> {
+        let mut lines = Vec::new();
+        for speaker in [&self.config.speaker1, &self.config.speaker2] {
+            lines.extend(profile_lines(speaker));
+            lines.push(Line::default());
+        }
+
+        let directions = &self.config.directions;
+        lines.push(Line::from(directions.scene_name.as_str().bold()));
+        if !directions.mood.is_empty() {
+            lines.push(Line::from(format!("Mood: {}", directions.mood)));
+        }
+        if !directions.goal.is_empty() {
+            lines.push(Line::from(format!("Goal: {}", directions.goal)));
+        }
+        if !self.notes.is_empty() {
+            lines.push(Line::default());
+            lines.push(Line::from("Director notes".bold()));
+            for note in &self.notes {
+                lines.push(Line::from(format!("- {}", note)));
+            }
+        }
+
+        Paragraph::new(Text::from(lines))
+            .block(Block::bordered().title(" Scene "))
+            .wrap(Wrap { trim: true })
+    }
+
+    fn draw_transcript(&self, frame: &mut Frame, area: Rect) {
+        let mut lines: Vec<Line> = self
+            .exchanges
+            .iter()
+            .map(|e| {
+                let style = if e.stats.is_some() {
+                    Style::new()
+                } else {
+                    Style::new().dim()
+                };
+                speaker_line(&e.speaker, &e.content, style)
+            })
+            .collect();
+
+        match (&self.mode, &self.pending) {
+            (Mode::Generating, _) => lines.push(speaker_line(
+                &self.streaming_speaker,
+                &format!("{}▌", self.streaming),
+                Style::new().fg(Color::Yellow),
+            )),
+            (_, Some(pending)) => lines.push(speaker_line(
+                &pending.speaker,
+                &pending.content,
+                Style::new().fg(Color::Cyan),
+            )),
+            _ => {}
+        }
+
+        // Keep the newest line in view
+        let inner_width = area.width.saturating_sub(2).max(1) as usize;
+        let rows: usize = lines
+            .iter()
+            .map(|line| line.width().div_ceil(inner_width).max(1))
+            .sum();
+        let scroll = rows.saturating_sub(area.height.saturating_sub(2) as usize);
+
+        let title = format!(
+            " Transcript: turn {}/{} ",
+            self.generated_turns(),
+            self.config.scene.turns
+        );
+        let transcript = Paragraph::new(Text::from(lines))
+            .block(Block::bordered().title(title))
+            .wrap(Wrap { trim: false })
+            .scroll((scroll.min(u16::MAX as usize) as u16, 0));
+        frame.render_widget(transcript, area);
+    }
+
+    fn stats(&self) -> Paragraph<'_> {
+        let latest = self
+            .pending
+            .as_ref()
+            .or_else(|| self.exchanges.iter().rev().find(|e| e.stats.is_some()))
+            .and_then(|e| e.stats.as_ref());
+
+        let turn_line = match latest {
+            Some(stats) => format_stats(stats),
+            None => "No turns generated yet".to_string(),
+        };
+
+        let (prompt, completion, wall) = self
+            .exchanges
+            .iter()
+            .filter_map(|e| e.stats.as_ref())
+            .fold((0, 0, Duration::ZERO), |(p, c, w), s| {
+                (
+                    p + s.prompt_tokens,
+                    c + s.completion_tokens,
+                    w + s.wall_time,
+                )
+            });
+        let session_line = format!(
+            "Session: {} prompt + {} completion tokens, {:.1}s",
+            prompt,
+            completion,
+            wall.as_secs_f64()
+        );
+
+        let mut lines = vec![Line::from(turn_line), Line::from(session_line)];
+        if !self.status.is_empty() {
+            lines[1] = Line::from(vec![
+                Span::raw(lines[1].to_string()),
+                Span::raw("  "),
+                Span::styled(self.status.as_str(), Style::new().fg(Color::Yellow)),
+            ]);
+        }
+
+        Paragraph::new(Text::from(lines)).block(Block::bordered().title(" Turn stats "))
+    }
+
+    fn help_line(&self) -> Line<'_> {
+        let keys = match self.mode {
+            Mode::Generating => "s stop & save · q quit",
+            Mode::Review if self.pending.is_some() => {
+                "a accept · r regenerate · e edit · n note · s stop & save · q quit"
+            }
+            Mode::Review => "r retry · n note · s stop & save · q quit",
+            Mode::Editing(_) | Mode::Note(_) => "Enter confirm · Esc cancel",
+            Mode::Done => "s save · g one more turn · n note · q quit without saving",
+        };
+        Line::from(keys).dim()
+    }
+}
+
+fn profile_lines(speaker: &Speaker) -> Vec<Line<'_>> {
+    let mut lines = vec![Line::from(speaker.name.as_str().bold())];
+    for (label, value) in [
+        ("Personality", &speaker.personality),
+        ("Style", &speaker.speaking_style),
+        ("Wants", &speaker.motivations),
+    ] {
+        if !value.is_empty() {
+            lines.push(Line::from(format!("{}: {}", label, value)));
+        }
+    }
+    lines
+}
+
+fn speaker_line<'a>(speaker: &'a str, content: &str, style: Style) -> Line<'a> {
+    Line::from(vec![
+        Span::styled(format!("{}: ", speaker), style.bold()),
+        Span::styled(content.to_string(), style),
+    ])
+}
+
+fn input_box<'a>(title: &'a str, buffer: &str) -> Paragraph<'a> {
+    Paragraph::new(format!("{}▌", buffer))
+        .block(Block::bordered().title(format!(" {} ", title)))
+        .wrap(Wrap { trim: false })
+}
+
+fn format_stats(stats: &ChatStats) -> String {
+    format!(
+        "Last turn: {} prompt / {} completion tokens · {:.1} tok/s · {:.1}s · {} retries",
+        stats.prompt_tokens,
+        stats.completion_tokens,
+        stats.tokens_per_second(),
+        stats.wall_time.as_secs_f64(),
+        stats.retries
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(turns: usize) -> App {
+        let config = DialogConfig::builder()
+            .speaker1(Speaker::new("Maya"))
+            .speaker2(Speaker::new("Dev"))
+            .line("Maya", "Ready?")
+            .turns(turns)
+            .build()
+            .unwrap();
+        App {
+            exchanges: vec![DialogExchange {
+                speaker: "Maya".to_string(),
+                content: "Ready?".to_string(),
+                stats: None,
+            }],
+            config,
+            pending: None,
+            streaming_speaker: String::new(),
+            streaming: String::new(),
+            notes: Vec::new(),
+            mode: Mode::Generating,
+            status: String::new(),
+        }
+    }
+
+    fn generated(content: &str) -> Result<DialogExchange> {
+        Ok(DialogExchange {
+            speaker: "Dev".to_string(),
+            content: content.to_string(),
+            stats: Some(ChatStats::default()),
+        })
+    }
+
+    fn press(app: &mut App, code: KeyCode) -> Action {
+        app.on_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            press(app, KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn accepting_a_line_generates_the_next_until_the_scene_is_done() {
+        let mut app = app(2);
+        app.on_turn_finished(generated("Always."));
+
+        assert!(matches!(
+            press(&mut app, KeyCode::Char('a')),
+            Action::Generate
+        ));
+        assert_eq!(app.exchanges.len(), 2);
+
+        app.on_turn_finished(generated("Let's go."));
+        assert!(matches!(press(&mut app, KeyCode::Enter), Action::Continue));
+        assert!(matches!(app.mode, Mode::Done));
+        assert_eq!(app.generated_turns(), 2);
+        assert!(matches!(press(&mut app, KeyCode::Enter), Action::Save));
+    }
+
+    #[test]
+    fn regenerating_leaves_the_pending_line_unaccepted() {
+        let mut app = app(2);
+        app.on_turn_finished(generated("Always."));
+
+        assert!(matches!(
+            press(&mut app, KeyCode::Char('r')),
+            Action::Generate
+        ));
+        assert_eq!(app.exchanges.len(), 1);
+    }
+
+    #[test]
+    fn editing_replaces_the_pending_line() {
+        let mut app = app(2);
+        app.on_turn_finished(generated("Always."));
+
+        press(&mut app, KeyCode::Char('e'));
+        assert!(matches!(&app.mode, Mode::Editing(text) if text == "Always."));
+        for _ in 0.."Always.".len() {
+            press(&mut app, KeyCode::Backspace);
+        }
+        type_text(&mut app, " Never. ");
+        press(&mut app, KeyCode::Enter);
+
+        assert!(matches!(app.mode, Mode::Review));
+        assert_eq!(app.pending.as_ref().unwrap().content, "Never.");
+    }
+
+    #[test]
+    fn escape_cancels_an_edit() {
+        let mut app = app(2);
+        app.on_turn_finished(generated("Always."));
+
+        press(&mut app, KeyCode::Char('e'));
+        type_text(&mut app, "!!");
+        press(&mut app, KeyCode::Esc);
+
+        assert!(matches!(app.mode, Mode::Review));
+        assert_eq!(app.pending.as_ref().unwrap().content, "Always.");
+    }
+
+    #[test]
+    fn notes_are_collected_and_blank_ones_ignored() {
+        let mut app = app(2);
+        app.on_turn_finished(generated("Always."));
+
+        press(&mut app, KeyCode::Char('n'));
+        type_text(&mut app, "be terse");
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char('n'));
+        type_text(&mut app, "  ");
+        press(&mut app, KeyCode::Enter);
+
+        assert_eq!(app.notes, ["be terse"]);
+        assert!(matches!(app.mode, Mode::Review));
+    }
+
+    #[test]
+    fn typed_letters_are_text_not_commands() {
+        let mut app = app(2);
+        app.on_turn_finished(generated("Always."));
+
+        press(&mut app, KeyCode::Char('n'));
+        assert!(matches!(
+            press(&mut app, KeyCode::Char('q')),
+            Action::Continue
+        ));
+        assert!(matches!(
+            press(&mut app, KeyCode::Char('s')),
+            Action::Continue
+        ));
+        assert!(matches!(&app.mode, Mode::Note(text) if text == "qs"));
+    }
+
+    #[test]
+    fn quitting_and_saving_while_generating() {
+        let mut app = app(2);
+        assert!(matches!(
+            press(&mut app, KeyCode::Char('a')),
+            Action::Continue
+        ));
+        assert!(matches!(press(&mut app, KeyCode::Char('s')), Action::Save));
+        assert!(matches!(press(&mut app, KeyCode::Esc), Action::Quit));
+
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        app.mode = Mode::Note(String::new());
+        assert!(matches!(app.on_key(ctrl_c), Action::Quit));
+    }
+
+    #[test]
+    fn a_failed_turn_leaves_nothing_to_accept() {
+        let mut app = app(2);
+        app.on_turn_finished(Err(crate::error::DialogGenError::GenerationFailed(
+            "empty reply".to_string(),
+        )));
+
+        assert!(app.status.starts_with("Turn failed"));
+        assert!(matches!(
+            press(&mut app, KeyCode::Char('a')),
+            Action::Continue
+        ));
+        assert!(matches!(
+            press(&mut app, KeyCode::Char('r')),
+            Action::Generate
+        ));
+    }
+}
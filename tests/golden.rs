@@ -0,0 +1,230 @@
+//! Golden-file regression tests for prompt assembly
+//!
+//! Every scene under `fixtures/` is run through a deterministic backend that
+//! records each request and answers with canned replies. The recorded system
+//! prompts and message histories are compared with the checked-in files in
+//! `fixtures/<scene>/golden/`, so prompt changes show up as reviewable diffs.
+//! The same goes for the line-mode edit and the result it applies, and for
+//! every export format of the generated dialog.
+//!
+//! Regenerate the golden files after an intentional prompt change with:
+//!
+//!     UPDATE_GOLDEN=1 cargo test golden
+
+use dialog_gen::editor::EditMode;
+use dialog_gen::formats::{ExportFormat, ExportOptions};
+use dialog_gen::{
+    ChatBackend, ChatMessage, ChatOptions, ChatResult, ChatStats, DialogConfig, DialogOrchestrator,
+    GeneratedDialog, OutputWriter, PodcastEditor, Result,
+};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Records every request and replies with numbered canned lines.
+/// A reply that looks like a script is returned for editor requests, and
+/// fixed line edits for line-mode editor requests.
+#[derive(Clone, Default)]
+struct RecordingBackend {
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl RecordingBackend {
+    fn take_transcript(&self) -> String {
+        std::mem::take(&mut *self.requests.lock().unwrap()).concat()
+    }
+}
+
+impl ChatBackend for RecordingBackend {
+    fn model(&self) -> &str {
+        "recording"
+    }
+
+    async fn chat(
+        &self,
+        system_prompt: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+    ) -> Result<ChatResult> {
+        let mut requests = self.requests.lock().unwrap();
+        let number = requests.len() + 1;
+
+        let seed = options.seed.map_or("none".to_string(), |s| s.to_string());
+        let mut record = format!(
+            "=== Request {} (temperature {}, seed {}) ===\n--- system ---\n{}\n",
+            number, options.temperature, seed, system_prompt
+        );
+        for message in messages {
+            let _ = write!(record, "--- {} ---\n{}\n", message.role, message.content);
+        }
+        record.push('\n');
+        requests.push(record);
+
+        let content = if system_prompt.contains("Reply with JSON only") {
+            r#"{"edits": [{"line": 0, "action": "insert_after", "text": "Inserted opener."}, {"line": 1, "action": "keep"}, {"line": 2, "action": "rewrite", "text": "Rewritten line two."}, {"line": 3, "action": "delete"}]}"#
+                .to_string()
+        } else if system_prompt.contains("podcast editor") {
+            "Speaker One: Edited line one.\n\nSpeaker Two: Edited line two.".to_string()
+        } else {
+            format!("Canned reply {}.", number)
+        };
+
+        Ok(ChatResult {
+            content,
+            stats: ChatStats::default(),
+        })
+    }
+}
+
+/// The dialog as "Speaker: line" lines
+fn script(dialog: &GeneratedDialog) -> String {
+    dialog
+        .exchanges
+        .iter()
+        .map(|e| format!("{}: {}\n", e.speaker, e.content))
+        .collect()
+}
+
+/// Every export format of `dialog`, concatenated with a header per file
+fn exports(dir: &Path, dialog: &GeneratedDialog, config: &DialogConfig) -> String {
+    let scene = dir.file_name().unwrap().to_string_lossy();
+    let out = std::env::temp_dir().join(format!(
+        "dialog-gen-golden-{}-{}",
+        std::process::id(),
+        scene
+    ));
+    let writer = OutputWriter::new(out.clone());
+    fs::create_dir_all(&out).unwrap();
+    let dialog_path = out.join("generated-dialog1.txt");
+
+    let mut files = Vec::new();
+    for format in ExportFormat::ALL {
+        let path = writer
+            .write_export(
+                &dialog_path,
+                *format,
+                dialog,
+                config,
+                &ExportOptions::default(),
+            )
+            .expect("export");
+        if path.is_dir() {
+            let mut entries: Vec<PathBuf> = fs::read_dir(&path)
+                .unwrap()
+                .flatten()
+                .map(|e| e.path())
+                .collect();
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(path);
+        }
+    }
+
+    let mut combined = String::new();
+    for file in files {
+        let name = file.strip_prefix(&out).unwrap().display().to_string();
+        let _ = write!(
+            combined,
+            ">>> {}\n{}\n",
+            name,
+            fs::read_to_string(&file).unwrap()
+        );
+    }
+    let _ = fs::remove_dir_all(&out);
+    combined
+}
+
+fn fixture_dirs() -> Vec<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    let mut dirs: Vec<PathBuf> = fs::read_dir(&root)
+        .expect("fixtures directory")
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Compare against a golden file, or rewrite it when UPDATE_GOLDEN is set.
+/// Returns a description of the first difference on mismatch.
+fn check_golden(path: &Path, actual: &str) -> Option<String> {
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, actual).unwrap();
+        return None;
+    }
+
+    let expected = match fs::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(_) => return Some(format!("{}: missing golden file", path.display())),
+    };
+
+    if expected == actual {
+        return None;
+    }
+
+    let line = expected
+        .lines()
+        .zip(actual.lines())
+        .position(|(e, a)| e != a)
+        .unwrap_or_else(|| expected.lines().count().min(actual.lines().count()));
+
+    Some(format!(
+        "{}: differs at line {}\n  expected: {:?}\n  actual:   {:?}",
+        path.display(),
+        line + 1,
+        expected.lines().nth(line).unwrap_or("<end of file>"),
+        actual.lines().nth(line).unwrap_or("<end of file>"),
+    ))
+}
+
+#[tokio::test]
+async fn golden_prompts_match_fixtures() {
+    let mut failures = Vec::new();
+
+    for dir in fixture_dirs() {
+        let config = DialogConfig::load(&dir).expect("fixture config");
+        let backend = RecordingBackend::default();
+
+        let dialog = DialogOrchestrator::new(backend.clone(), config.clone())
+            .generate()
+            .await
+            .expect("generation");
+        let generate = backend.take_transcript();
+
+        PodcastEditor::new(backend.clone())
+            .edit(&dialog)
+            .await
+            .expect("editing");
+        let edit = backend.take_transcript();
+
+        let edited = PodcastEditor::new(backend.clone())
+            .with_mode(EditMode::Lines)
+            .edit(&dialog)
+            .await
+            .expect("line editing");
+        let edit_lines = format!(
+            "{}=== Result ===\n{}",
+            backend.take_transcript(),
+            script(&edited)
+        );
+
+        let golden = dir.join("golden");
+        failures.extend(check_golden(&golden.join("generate.txt"), &generate));
+        failures.extend(check_golden(&golden.join("edit.txt"), &edit));
+        failures.extend(check_golden(&golden.join("edit-lines.txt"), &edit_lines));
+        failures.extend(check_golden(
+            &golden.join("exports.txt"),
+            &exports(&dir, &dialog, &config),
+        ));
+    }
+
+    assert!(
+        failures.is_empty(),
+        "assembled prompts changed (run with UPDATE_GOLDEN=1 to accept):\n{}",
+        failures.join("\n")
+    );
+}